@@ -21,9 +21,12 @@ invalid usage, kind of like valgrind.
 
 ### Atomic ordering
 
-The library currently uses sequential consistency for all atomic operations. This is to be somewhat
-conservative for now. If you understand atomic memory orderings really well, please feel free
-to suggest possible relaxations, I know there are many. Just motivate them well.
+The atomic operations on the channel state use acquire/release semantics, the weakest orderings
+that are correct for each transition. The reasoning is documented next to the implementation
+description in `src/lib.rs`. The loom models in `tests/transitions.rs` race sending, acked sending,
+dropping the sender and checking or polling whether the channel is closed against every receive
+operation. If you find a transition that could be relaxed further, or one that is too weak, please
+open an issue. Just motivate it well.
 
 ## My message passing frustrations and dreams
 
@@ -33,10 +36,10 @@ library before coming to the problems:
 
 * Have dedicated channel primitives for all the common channel types:
   * Oneshot spsc (this is what this crate implements)
-  * Rendezvous spsc and mpmc
-  * Bounded mpmc
-  * Unbounded mpmc
-  * Broadcast mpmc (every consumer see every message)
+  * Rendezvous spsc and mpmc (the `rendezvous` module)
+  * Bounded mpmc (the `bounded` module)
+  * Unbounded mpmc (the `unbounded` module)
+  * Broadcast mpmc (every consumer see every message) (the `broadcast` module)
 * All the send and receive methods that can't be completed in a lock-free manner should have both
   thread blocking and async versions of themselves. The async version should be executor agnostic,
   not depend on any one executor and work on all of them.
@@ -1,3 +1,9 @@
+// To compare the atomic orderings of the channel against SeqCst, save a baseline built with
+// `--cfg oneshot_seqcst` and compare the regular build against it:
+//
+//     RUSTFLAGS="--cfg oneshot_seqcst" cargo bench -- --save-baseline seqcst
+//     cargo bench -- --baseline seqcst
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::mem;
 use std::time::{Duration, Instant};
@@ -74,6 +80,9 @@ fn bench(c: &mut Criterion) {
     bench_try_recv(c);
     bench_recv_deadline_now(c);
     bench_recv_timeout_zero(c);
+    bench_pool(c);
+    bench_cross_thread(c);
+    bench_recv_strategy(c);
 }
 
 fn bench_try_recv(c: &mut Criterion) {
@@ -120,3 +129,133 @@ fn bench_recv_timeout_zero(c: &mut Criterion) {
         });
     }
 }
+
+fn bench_pool(c: &mut Criterion) {
+    {
+        let mut group = c.benchmark_group("pool_create_send_and_recv");
+        group.bench_function("channel", |b| {
+            b.iter(|| {
+                let (sender, receiver) = oneshot::channel();
+                sender.send(black_box(1234567u128)).unwrap();
+                receiver.try_recv().unwrap()
+            });
+        });
+        let pool = oneshot::ChannelPool::new();
+        group.bench_function("ChannelPool", |b| {
+            b.iter(|| {
+                let (sender, receiver) = pool.channel();
+                sender.send(black_box(1234567u128)).unwrap();
+                receiver.try_recv().unwrap()
+            });
+        });
+        group.finish();
+    }
+    {
+        // Many channels alive at the same time, so the allocations can't just be reused
+        // by the global allocator from one iteration to the next.
+        const CHANNELS: usize = 64;
+        let mut group = c.benchmark_group("pool_create_send_and_recv_many");
+        group.bench_function("channel", |b| {
+            b.iter(|| {
+                let receivers: Vec<_> = (0..CHANNELS)
+                    .map(|i| {
+                        let (sender, receiver) = oneshot::channel();
+                        sender.send(black_box(i)).unwrap();
+                        receiver
+                    })
+                    .collect();
+                receivers
+                    .into_iter()
+                    .map(|receiver| receiver.try_recv().unwrap())
+                    .sum::<usize>()
+            });
+        });
+        let pool = oneshot::ChannelPool::with_capacity(CHANNELS);
+        group.bench_function("ChannelPool", |b| {
+            b.iter(|| {
+                let receivers: Vec<_> = (0..CHANNELS)
+                    .map(|i| {
+                        let (sender, receiver) = pool.channel();
+                        sender.send(black_box(i)).unwrap();
+                        receiver
+                    })
+                    .collect();
+                receivers
+                    .into_iter()
+                    .map(|receiver| receiver.try_recv().unwrap())
+                    .sum::<usize>()
+            });
+        });
+        group.finish();
+    }
+}
+
+/// Runs `f` with a channel to a thread that sends a message on every `oneshot::Sender` it gets.
+fn with_responder(f: impl FnOnce(&std::sync::mpsc::Sender<oneshot::Sender<u128>>)) {
+    let (request_sender, request_receiver) = std::sync::mpsc::channel::<oneshot::Sender<u128>>();
+    let worker = std::thread::spawn(move || {
+        for sender in request_receiver {
+            sender.send(black_box(1234567u128)).unwrap();
+        }
+    });
+    f(&request_sender);
+    mem::drop(request_sender);
+    worker.join().unwrap();
+}
+
+/// Round trips to another thread. These exercise the acquire/release handoff between the
+/// sending and receiving core, which is where the orderings of the state transitions show up,
+/// in particular on weakly ordered hardware such as ARM.
+fn bench_cross_thread(c: &mut Criterion) {
+    with_responder(|request_sender| {
+        let mut group = c.benchmark_group("cross_thread_send_and_recv");
+        group.bench_function("recv", |b| {
+            b.iter(|| {
+                let (sender, receiver) = oneshot::channel();
+                request_sender.send(sender).unwrap();
+                receiver.recv().unwrap()
+            });
+        });
+        group.bench_function("try_recv_spin", |b| {
+            b.iter(|| {
+                let (sender, receiver) = oneshot::channel();
+                request_sender.send(sender).unwrap();
+                loop {
+                    match receiver.try_recv() {
+                        Ok(message) => break message,
+                        Err(oneshot::TryRecvError::Empty) => core::hint::spin_loop(),
+                        Err(_) => unreachable!(),
+                    }
+                }
+            });
+        });
+        group.finish();
+    });
+}
+
+/// Round trip latency to another thread, with different amounts of busy-waiting before the
+/// receiving thread blocks.
+fn bench_recv_strategy(c: &mut Criterion) {
+    let strategies = [
+        ("block", oneshot::RecvStrategy::new()),
+        ("spin_64", oneshot::RecvStrategy::new().spin(64)),
+        ("spin_1024", oneshot::RecvStrategy::new().spin(1024)),
+        (
+            "spin_64_yield_16",
+            oneshot::RecvStrategy::new().spin(64).yield_now(16),
+        ),
+    ];
+    with_responder(|request_sender| {
+        let mut group = c.benchmark_group("cross_thread_round_trip");
+        for (name, strategy) in strategies {
+            group.bench_function(name, |b| {
+                b.iter(|| {
+                    let (sender, receiver) = oneshot::channel();
+                    request_sender.send(sender).unwrap();
+                    receiver.recv_with_strategy(strategy).unwrap()
+                });
+            });
+        }
+        group.finish();
+    });
+}
@@ -0,0 +1,8 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    // Declares the custom cfgs used for testing, so newer compilers don't warn about them.
+    // Older Cargo versions silently ignore this instruction.
+    println!("cargo:rustc-check-cfg=cfg(loom)");
+    println!("cargo:rustc-check-cfg=cfg(oneshot_test_delay)");
+    println!("cargo:rustc-check-cfg=cfg(oneshot_seqcst)");
+}
@@ -1,7 +1,13 @@
-use std::mem;
-
+#[cfg(feature = "alloc")]
 fn main() {
+    use std::mem;
+
     let (sender, receiver) = oneshot::channel();
     assert!(sender.send(19i128).is_ok());
     mem::drop(receiver);
 }
+
+#[cfg(not(feature = "alloc"))]
+fn main() {
+    panic!("This example is only for when the \"alloc\" feature is used");
+}
@@ -1,8 +1,14 @@
-use std::mem;
-
+#[cfg(feature = "alloc")]
 fn main() {
+    use std::mem;
+
     let (sender, receiver) = oneshot::channel();
     mem::drop(receiver);
     let send_error = sender.send(5u128).unwrap_err();
     assert_eq!(send_error.into_inner(), 5);
 }
+
+#[cfg(not(feature = "alloc"))]
+fn main() {
+    panic!("This example is only for when the \"alloc\" feature is used");
+}
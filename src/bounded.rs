@@ -0,0 +1,448 @@
+//! A bounded mpmc channel, a queue holding up to a fixed number of messages.
+//!
+//! Sending on a full channel waits until a receiver has made room, and receiving from an empty
+//! channel waits until a sender has sent a message. Both [`Sender`] and [`Receiver`] can be
+//! cloned. Every message is taken by exactly one receiver, in the order the messages were sent.
+//!
+//! Every operation that can wait exists in a blocking version, with and without a timeout, and
+//! in an async version. They can be mixed freely. Threads can send to async tasks and the other
+//! way around, without involving any particular async runtime.
+//!
+//! ```rust
+//! use std::thread;
+//!
+//! let (sender, receiver) = oneshot::bounded::channel(2);
+//!
+//! let handle = thread::spawn(move || {
+//!     for i in 0..10u32 {
+//!         // Blocks while two messages are waiting in the channel.
+//!         sender.send(i).unwrap();
+//!     }
+//! });
+//!
+//! // Once the sender is gone and the channel is empty, `recv` returns an error.
+//! let received: Vec<u32> = std::iter::from_fn(|| receiver.recv().ok()).collect();
+//! assert_eq!(received, (0..10).collect::<Vec<_>>());
+//! handle.join().unwrap();
+//! ```
+
+pub use super::recv_errors::{RecvError, RecvTimeoutError, TryRecvError};
+pub use super::send_errors::{SendError, SendTimeoutError, TrySendError};
+use super::waiters::{self, Token, Waiters};
+use super::ReceiverWaker;
+use alloc::collections::VecDeque;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task;
+use core::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(loom)]
+use loom::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+use std::sync::{Arc, Mutex};
+
+/// Creates a new bounded channel holding up to `capacity` messages, returning the first sender
+/// and receiver of it.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero. Use a [`rendezvous`](crate::rendezvous) channel for a channel
+/// without capacity.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "bounded channel with zero capacity");
+    let shared = Arc::new(Mutex::new(Shared {
+        queue: VecDeque::with_capacity(capacity),
+        capacity,
+        senders: Waiters::default(),
+        receivers: Waiters::default(),
+        sender_count: 1,
+        receiver_count: 1,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    queue: VecDeque<T>,
+    capacity: usize,
+    /// Senders waiting for room in the queue.
+    senders: Waiters,
+    /// Receivers waiting for a message in the queue.
+    receivers: Waiters,
+    sender_count: usize,
+    receiver_count: usize,
+}
+
+impl<T> Shared<T> {
+    fn is_full(&self) -> bool {
+        self.queue.len() == self.capacity
+    }
+}
+
+/// The sending half of a bounded channel. Can be cloned to send from many places.
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The receiving half of a bounded channel. Can be cloned to receive in many places.
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `message` on the channel if there is room for it, without blocking. Returns:
+    ///  * `Ok(())` if the message was put in the channel.
+    ///  * `Err(Full(message))` if the channel is full.
+    ///  * `Err(Disconnected(message))` if all receivers have been dropped.
+    pub fn try_send(&self, message: T) -> Result<(), TrySendError<T>> {
+        let mut message = Some(message);
+        match self.poll_send(&mut message, &mut None, None) {
+            Poll::Ready(Ok(())) => Ok(()),
+            Poll::Ready(Err(SendTimeoutError::Timeout(message))) => {
+                Err(TrySendError::Full(message))
+            }
+            Poll::Ready(Err(SendTimeoutError::Disconnected(message))) => {
+                Err(TrySendError::Disconnected(message))
+            }
+            Poll::Pending => unreachable!("send polled without a waker must complete"),
+        }
+    }
+
+    /// Sends `message` on the channel, blocking the thread while the channel is full.
+    ///
+    /// Returns an error containing the message if all receivers have been dropped.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.send_until(message, None).map_err(|error| match error {
+            SendTimeoutError::Disconnected(message) => SendError(message),
+            SendTimeoutError::Timeout(_) => unreachable!("send without a deadline can't time out"),
+        })
+    }
+
+    /// Like [`Sender::send`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(())` if the message was put in the channel before the timeout was reached.
+    ///  * `Err(Timeout(message))` if the channel stayed full until the timeout was reached.
+    ///  * `Err(Disconnected(message))` if all receivers have been dropped.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking send operation.
+    pub fn send_timeout(&self, message: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(message, Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Sender::send`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(())` if the message was put in the channel before the deadline was reached.
+    ///  * `Err(Timeout(message))` if the channel stayed full until the deadline was reached.
+    ///  * `Err(Disconnected(message))` if all receivers have been dropped.
+    pub fn send_deadline(&self, message: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(message, Some(deadline))
+    }
+
+    /// Sends `message` on the channel, waiting while the channel is full. The async version of
+    /// [`Sender::send`].
+    ///
+    /// Dropping the returned future before it completes drops the message without sending it.
+    #[cfg(feature = "async")]
+    pub fn send_async(&self, message: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            message: Some(message),
+            token: None,
+        }
+    }
+
+    fn send_until(&self, message: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
+        let (mut message, mut token) = (Some(message), None);
+        waiters::block(deadline, |waker| {
+            self.poll_send(&mut message, &mut token, waker)
+        })
+    }
+
+    /// Puts `message` in the queue if there is room for it. Registers `waker` if not, leaving
+    /// the message in place. Without a waker, gives up waiting. Once this returns `Ready` the
+    /// sender is not waiting anymore.
+    fn poll_send(
+        &self,
+        message: &mut Option<T>,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<(), SendTimeoutError<T>>> {
+        let value = message.take().expect("send polled after completion");
+        let mut shared = waiters::lock(&self.shared);
+        let (result, receiver) = if shared.receiver_count == 0 {
+            (
+                Poll::Ready(Err(SendTimeoutError::Disconnected(value))),
+                None,
+            )
+        } else if !shared.is_full() {
+            shared.queue.push_back(value);
+            (Poll::Ready(Ok(())), shared.receivers.notify_one())
+        } else if let Some(waker) = waker {
+            shared.senders.register(token, waker);
+            *message = Some(value);
+            (Poll::Pending, None)
+        } else {
+            (Poll::Ready(Err(SendTimeoutError::Timeout(value))), None)
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                shared.senders.take(token);
+            }
+        }
+        drop(shared);
+
+        if let Some(receiver) = receiver {
+            receiver.unpark();
+        }
+        result
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.sender_count -= 1;
+        if shared.sender_count == 0 {
+            let receivers = shared.receivers.notify_all();
+            drop(shared);
+            waiters::wake_all(receivers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Takes the oldest message out of the channel without blocking. Returns:
+    ///  * `Ok(message)` if there was a message in the channel.
+    ///  * `Err(Empty)` if the channel is empty, but senders are still alive.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.poll_recv(&mut None, None) {
+            Poll::Ready(Ok(message)) => Ok(message),
+            Poll::Ready(Err(RecvTimeoutError::Timeout)) => Err(TryRecvError::Empty),
+            Poll::Ready(Err(RecvTimeoutError::Disconnected)) => Err(TryRecvError::Disconnected),
+            Poll::Pending => unreachable!("recv polled without a waker must complete"),
+        }
+    }
+
+    /// Takes the oldest message out of the channel, blocking the thread while the channel is
+    /// empty. Returns an error if the channel is empty and all senders have been dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(message)` if there was a message in the channel before the timeout was reached.
+    ///  * `Err(Timeout)` if the channel stayed empty until the timeout was reached.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking receive operation.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(message)` if there was a message in the channel before the deadline was reached.
+    ///  * `Err(Timeout)` if the channel stayed empty until the deadline was reached.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    /// Takes the oldest message out of the channel, waiting while the channel is empty. The
+    /// async version of [`Receiver::recv`].
+    ///
+    /// Dropping the returned future before it completes never loses a message. Messages are
+    /// only taken out of the channel by the poll that completes the future.
+    #[cfg(feature = "async")]
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            token: None,
+        }
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut token = None;
+        waiters::block(deadline, |waker| self.poll_recv(&mut token, waker))
+    }
+
+    /// Takes the oldest message out of the queue, if there is one. Registers `waker` if not.
+    /// Without a waker, gives up waiting. Once this returns `Ready`, the receiver is not
+    /// waiting anymore.
+    fn poll_recv(
+        &self,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<T, RecvTimeoutError>> {
+        let mut shared = waiters::lock(&self.shared);
+        let (result, sender) = match shared.queue.pop_front() {
+            Some(message) => (Poll::Ready(Ok(message)), shared.senders.notify_one()),
+            None if shared.sender_count == 0 => {
+                (Poll::Ready(Err(RecvTimeoutError::Disconnected)), None)
+            }
+            None => match waker {
+                Some(waker) => {
+                    shared.receivers.register(token, waker);
+                    (Poll::Pending, None)
+                }
+                None => (Poll::Ready(Err(RecvTimeoutError::Timeout)), None),
+            },
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                shared.receivers.take(token);
+            }
+        }
+        drop(shared);
+
+        if let Some(sender) = sender {
+            sender.unpark();
+        }
+        result
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.receiver_count -= 1;
+        if shared.receiver_count == 0 {
+            // Nobody can receive the remaining messages anymore. Drop them outside the lock.
+            let messages = core::mem::take(&mut shared.queue);
+            let senders = shared.senders.notify_all();
+            drop(shared);
+            waiters::wake_all(senders);
+            drop(messages);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// A future sending a message on a bounded channel. Created by [`Sender::send_async`].
+#[cfg(feature = "async")]
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    message: Option<T>,
+    token: Option<Token>,
+}
+
+// The message is never pinned, it is moved into the channel once there is room.
+#[cfg(feature = "async")]
+impl<T> Unpin for SendFuture<'_, T> {}
+
+#[cfg(feature = "async")]
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.sender
+            .poll_send(
+                &mut this.message,
+                &mut this.token,
+                Some(ReceiverWaker::task_waker(cx)),
+            )
+            .map_err(|error| SendError(error.into_inner()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.token.is_some() {
+            let mut shared = waiters::lock(&self.sender.shared);
+            let ready = !shared.is_full();
+            let sender = shared.senders.cancel(self.token, ready);
+            drop(shared);
+            if let Some(sender) = sender {
+                sender.unpark();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for SendFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendFuture").finish_non_exhaustive()
+    }
+}
+
+/// A future receiving a message from a bounded channel. Created by [`Receiver::recv_async`].
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+    token: Option<Token>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.receiver
+            .poll_recv(&mut this.token, Some(ReceiverWaker::task_waker(cx)))
+            .map_err(|_| RecvError)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.token.is_some() {
+            let mut shared = waiters::lock(&self.receiver.shared);
+            let ready = !shared.queue.is_empty();
+            let receiver = shared.receivers.cancel(self.token, ready);
+            drop(shared);
+            if let Some(receiver) = receiver {
+                receiver.unpark();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for RecvFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvFuture").finish_non_exhaustive()
+    }
+}
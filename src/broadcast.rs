@@ -0,0 +1,435 @@
+//! A broadcast mpmc channel, where every receiver observes every message.
+//!
+//! The channel keeps the last `capacity` messages sent in a ring buffer. Every [`Receiver`]
+//! reads the messages at its own pace, getting a clone of each of them. Sending never waits.
+//! When the buffer is full, the oldest message is overwritten, and receivers that had not read
+//! it yet get a [`Lagged`](RecvError::Lagged) error telling how many messages they missed. They
+//! then continue with the oldest message still in the buffer.
+//!
+//! [`Sender`]s can be cloned. New receivers are created with [`Sender::subscribe`], and see
+//! the messages sent after subscribing. Cloning a [`Receiver`] creates a receiver at the same
+//! position in the channel.
+//!
+//! Receiving exists in a blocking version, with and without a timeout, and in an async
+//! version. Threads and async tasks can subscribe to the same channel, without involving any
+//! particular async runtime.
+//!
+//! ```rust
+//! use oneshot::broadcast::{self, RecvError};
+//! use std::thread;
+//!
+//! let (sender, mut receiver1) = broadcast::channel(16);
+//! let mut receiver2 = sender.subscribe();
+//!
+//! let handle = thread::spawn(move || {
+//!     let received: Vec<u32> = std::iter::from_fn(|| receiver2.recv().ok()).collect();
+//!     assert_eq!(received, [1, 2, 3]);
+//! });
+//!
+//! for i in 1..=3 {
+//!     sender.send(i).unwrap();
+//! }
+//! drop(sender);
+//!
+//! assert_eq!(receiver1.recv(), Ok(1));
+//! assert_eq!(receiver1.recv(), Ok(2));
+//! assert_eq!(receiver1.recv(), Ok(3));
+//! assert_eq!(receiver1.recv(), Err(RecvError::Disconnected));
+//! handle.join().unwrap();
+//! ```
+
+pub use super::send_errors::SendError;
+use super::waiters::{self, Token, Waiters};
+use super::ReceiverWaker;
+use alloc::collections::VecDeque;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task;
+use core::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(loom)]
+use loom::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+use std::sync::{Arc, Mutex};
+
+/// Creates a new broadcast channel keeping up to `capacity` messages for its receivers,
+/// returning the first sender and receiver of it.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn channel<T: Clone>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "broadcast channel with zero capacity");
+    let shared = Arc::new(Mutex::new(Shared {
+        buffer: VecDeque::with_capacity(capacity),
+        capacity,
+        first: 0,
+        receivers: Waiters::default(),
+        sender_count: 1,
+        receiver_count: 1,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared, next: 0 },
+    )
+}
+
+struct Shared<T> {
+    buffer: VecDeque<T>,
+    capacity: usize,
+    /// The position of the first message in the buffer. Positions count all messages ever
+    /// sent on the channel.
+    first: u64,
+    /// Receivers waiting for a message to be sent.
+    receivers: Waiters,
+    sender_count: usize,
+    receiver_count: usize,
+}
+
+impl<T> Shared<T> {
+    /// The position the next message sent will get.
+    fn end(&self) -> u64 {
+        self.first + self.buffer.len() as u64
+    }
+}
+
+/// The sending half of a broadcast channel. Can be cloned to send from many places.
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The receiving half of a broadcast channel. Receives every message sent after it subscribed.
+///
+/// Can be cloned to get another receiver at the same position in the channel.
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    /// The position of the next message this receiver will get.
+    next: u64,
+}
+
+impl<T: Clone> Sender<T> {
+    /// Sends `message` to all receivers. Never blocks. If the channel already holds `capacity`
+    /// messages, the oldest one is dropped to make room.
+    ///
+    /// Returns an error containing the message if there are no receivers.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        let mut shared = waiters::lock(&self.shared);
+        if shared.receiver_count == 0 {
+            return Err(SendError(message));
+        }
+        let overwritten = if shared.buffer.len() == shared.capacity {
+            shared.first += 1;
+            shared.buffer.pop_front()
+        } else {
+            None
+        };
+        shared.buffer.push_back(message);
+        let receivers = shared.receivers.notify_all();
+        drop(shared);
+
+        waiters::wake_all(receivers);
+        drop(overwritten);
+        Ok(())
+    }
+
+    /// Creates a new receiver, getting all messages sent from now on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        let mut shared = waiters::lock(&self.shared);
+        shared.receiver_count += 1;
+        let next = shared.end();
+        drop(shared);
+        Receiver {
+            shared: self.shared.clone(),
+            next,
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.sender_count -= 1;
+        if shared.sender_count == 0 {
+            let receivers = shared.receivers.notify_all();
+            drop(shared);
+            waiters::wake_all(receivers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receives the next message without blocking. Returns:
+    ///  * `Ok(message)` if there was a message this receiver has not seen yet.
+    ///  * `Err(Empty)` if this receiver has seen all messages, but senders are still alive.
+    ///  * `Err(Disconnected)` if this receiver has seen all messages and all senders have been
+    ///    dropped.
+    ///  * `Err(Lagged(n))` if `n` messages were overwritten before this receiver saw them.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.poll_recv(&mut None, None) {
+            Poll::Ready(Ok(message)) => Ok(message),
+            Poll::Ready(Err(RecvTimeoutError::Timeout)) => Err(TryRecvError::Empty),
+            Poll::Ready(Err(RecvTimeoutError::Disconnected)) => Err(TryRecvError::Disconnected),
+            Poll::Ready(Err(RecvTimeoutError::Lagged(n))) => Err(TryRecvError::Lagged(n)),
+            Poll::Pending => unreachable!("recv polled without a waker must complete"),
+        }
+    }
+
+    /// Receives the next message, blocking the thread until one is sent. Returns:
+    ///  * `Ok(message)` once there is a message this receiver has not seen yet.
+    ///  * `Err(Disconnected)` if this receiver has seen all messages and all senders have been
+    ///    dropped.
+    ///  * `Err(Lagged(n))` if `n` messages were overwritten before this receiver saw them.
+    pub fn recv(&mut self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|error| match error {
+            RecvTimeoutError::Disconnected => RecvError::Disconnected,
+            RecvTimeoutError::Lagged(n) => RecvError::Lagged(n),
+            RecvTimeoutError::Timeout => unreachable!("recv without a deadline can't time out"),
+        })
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(message)` if a message arrived before the timeout was reached.
+    ///  * `Err(Timeout)` if no message arrived before the timeout was reached.
+    ///  * `Err(Disconnected)` if this receiver has seen all messages and all senders have been
+    ///    dropped.
+    ///  * `Err(Lagged(n))` if `n` messages were overwritten before this receiver saw them.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking receive operation.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(message)` if a message arrived before the deadline was reached.
+    ///  * `Err(Timeout)` if no message arrived before the deadline was reached.
+    ///  * `Err(Disconnected)` if this receiver has seen all messages and all senders have been
+    ///    dropped.
+    ///  * `Err(Lagged(n))` if `n` messages were overwritten before this receiver saw them.
+    pub fn recv_deadline(&mut self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    /// Receives the next message, waiting until one is sent. The async version of
+    /// [`Receiver::recv`].
+    ///
+    /// Dropping the returned future before it completes never loses a message. The receiver
+    /// only moves on in the poll that completes the future.
+    #[cfg(feature = "async")]
+    pub fn recv_async(&mut self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            token: None,
+        }
+    }
+
+    fn recv_until(&mut self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut token = None;
+        waiters::block(deadline, |waker| self.poll_recv(&mut token, waker))
+    }
+
+    /// Clones the next message this receiver has not seen yet, if there is one. Registers
+    /// `waker` if not. Without a waker, gives up waiting. Once this returns `Ready`, the
+    /// receiver is not waiting anymore.
+    fn poll_recv(
+        &mut self,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<T, RecvTimeoutError>> {
+        let mut shared = waiters::lock(&self.shared);
+        let result = if self.next < shared.first {
+            let missed = shared.first - self.next;
+            self.next = shared.first;
+            Poll::Ready(Err(RecvTimeoutError::Lagged(missed)))
+        } else if self.next < shared.end() {
+            let message = shared.buffer[(self.next - shared.first) as usize].clone();
+            self.next += 1;
+            Poll::Ready(Ok(message))
+        } else if shared.sender_count == 0 {
+            Poll::Ready(Err(RecvTimeoutError::Disconnected))
+        } else if let Some(waker) = waker {
+            shared.receivers.register(token, waker);
+            Poll::Pending
+        } else {
+            Poll::Ready(Err(RecvTimeoutError::Timeout))
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                shared.receivers.take(token);
+            }
+        }
+        result
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+            next: self.next,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.receiver_count -= 1;
+        if shared.receiver_count == 0 {
+            // Receivers subscribing later won't see the buffered messages. Drop them outside
+            // the lock.
+            let messages = core::mem::take(&mut shared.buffer);
+            shared.first += messages.len() as u64;
+            drop(shared);
+            drop(messages);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// A future receiving a message from a broadcast channel. Created by [`Receiver::recv_async`].
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a mut Receiver<T>,
+    token: Option<Token>,
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.receiver
+            .poll_recv(&mut this.token, Some(ReceiverWaker::task_waker(cx)))
+            .map_err(|error| match error {
+                RecvTimeoutError::Disconnected => RecvError::Disconnected,
+                RecvTimeoutError::Lagged(n) => RecvError::Lagged(n),
+                RecvTimeoutError::Timeout => unreachable!("recv with a waker can't time out"),
+            })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        // Every waiting receiver is notified of every message, so there is no notification to
+        // hand on.
+        if let Some(token) = self.token {
+            waiters::lock(&self.receiver.shared).receivers.take(token);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for RecvFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvFuture").finish_non_exhaustive()
+    }
+}
+
+/// An error returned from [`Receiver::recv`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RecvError {
+    /// The receiver has seen all messages, and all senders have been dropped.
+    Disconnected,
+
+    /// The receiver fell behind, and this many messages were overwritten before it saw them.
+    /// The next receive gets the oldest message still in the channel.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvError::Disconnected => "receiving on a closed channel".fmt(f),
+            RecvError::Lagged(n) => write!(f, "receiver lagged behind and missed {} messages", n),
+        }
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// An error returned from [`Receiver::try_recv`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TryRecvError {
+    /// The receiver has seen all messages, but senders are still alive.
+    Empty,
+
+    /// The receiver has seen all messages, and all senders have been dropped.
+    Disconnected,
+
+    /// The receiver fell behind, and this many messages were overwritten before it saw them.
+    /// The next receive gets the oldest message still in the channel.
+    Lagged(u64),
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => "receiving on an empty channel".fmt(f),
+            TryRecvError::Disconnected => "receiving on a closed channel".fmt(f),
+            TryRecvError::Lagged(n) => {
+                write!(f, "receiver lagged behind and missed {} messages", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
+/// An error returned from [`Receiver::recv_timeout`] and [`Receiver::recv_deadline`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RecvTimeoutError {
+    /// No message arrived before the timeout was reached. The channel is still open.
+    Timeout,
+
+    /// The receiver has seen all messages, and all senders have been dropped.
+    Disconnected,
+
+    /// The receiver fell behind, and this many messages were overwritten before it saw them.
+    /// The next receive gets the oldest message still in the channel.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => "timed out waiting on channel".fmt(f),
+            RecvTimeoutError::Disconnected => "receiving on a closed channel".fmt(f),
+            RecvTimeoutError::Lagged(n) => {
+                write!(f, "receiver lagged behind and missed {} messages", n)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecvTimeoutError {}
@@ -0,0 +1,30 @@
+/// A snapshot of the state of a oneshot channel. Returned from [`Sender::state`] and
+/// [`Receiver::state`], and shown in their `Debug` output.
+///
+/// The other endpoint can change the state at any moment, so the snapshot might be outdated by
+/// the time it is looked at. The states that only one endpoint can leave are stable for the other
+/// one, for example a receiver seeing [`Message`](ChannelState::Message) can rely on the message
+/// staying in the channel until it receives it.
+///
+/// [`Sender::state`]: crate::Sender::state
+/// [`Receiver::state`]: crate::Receiver::state
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum ChannelState {
+    /// Both endpoints are alive, no message has been sent, and the receiver is not waiting for
+    /// one.
+    Empty,
+
+    /// A message has been sent, and the receiver has not yet taken it out of the channel.
+    Message,
+
+    /// Both endpoints are alive, no message has been sent, and the receiver is waiting for one.
+    Receiving {
+        /// True if a thread is blocked in one of the blocking receive or wait methods. False if
+        /// the receiver was polled asynchronously and its task waits to be woken up.
+        sync: bool,
+    },
+
+    /// The channel is closed. Either the sender was dropped before sending anything, the
+    /// message has already been received, or the receiver was dropped.
+    Disconnected,
+}
@@ -1,14 +1,21 @@
+#[cfg(feature = "alloc")]
 use super::{dealloc, Channel};
+#[cfg(feature = "alloc")]
+use allocator_api2::alloc::{Allocator, Global};
 use core::fmt;
+#[cfg(feature = "alloc")]
 use core::marker::PhantomData;
+#[cfg(feature = "alloc")]
 use core::mem;
+#[cfg(feature = "alloc")]
 use core::ptr::NonNull;
 
 /// An error returned when trying to send on a closed channel. Returned from
 /// [`Sender::send`] if the corresponding [`Receiver`] has already been dropped.
 ///
 /// The message that could not be sent can be retreived again with [`SendError::into_inner`].
-pub struct SendError<T> {
+#[cfg(feature = "alloc")]
+pub struct SendError<T, A: Allocator = Global> {
     channel_ptr: NonNull<Channel<T>>,
     /// Required due to the reasons outlined in
     /// [this section](https://doc.rust-lang.org/nomicon/dropck.html) of the nomicon, as well as
@@ -45,12 +52,17 @@ pub struct SendError<T> {
     /// drop(foo);
     /// ```
     _dropck: PhantomData<T>,
+    /// The allocator lives in the channel allocation, see `ChannelBlock`.
+    _alloc: PhantomData<A>,
 }
 
-unsafe impl<T: Send> Send for SendError<T> {}
-unsafe impl<T: Sync> Sync for SendError<T> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send, A: Allocator + Send> Send for SendError<T, A> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Sync, A: Allocator + Send> Sync for SendError<T, A> {}
 
-impl<T> SendError<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> SendError<T, A> {
     /// # Safety
     ///
     /// By calling this function, the caller semantically transfers ownership of the
@@ -61,6 +73,7 @@ impl<T> SendError<T> {
         Self {
             channel_ptr,
             _dropck: PhantomData,
+            _alloc: PhantomData,
         }
     }
 
@@ -80,7 +93,7 @@ impl<T> SendError<T> {
         let message = unsafe { channel.take_message() };
 
         // SAFETY: we own the channel
-        unsafe { dealloc(channel_ptr) };
+        unsafe { dealloc::<T, A>(channel_ptr) };
 
         message
     }
@@ -92,42 +105,72 @@ impl<T> SendError<T> {
     }
 }
 
-impl<T> Drop for SendError<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Drop for SendError<T, A> {
     fn drop(&mut self) {
         // SAFETY: we have ownership of the channel and require that the message is initialized
         // upon construction
         unsafe {
             self.channel_ptr.as_ref().drop_message();
-            dealloc(self.channel_ptr);
+            dealloc::<T, A>(self.channel_ptr);
         }
     }
 }
 
-impl<T> fmt::Display for SendError<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> fmt::Display for SendError<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         "sending on a closed channel".fmt(f)
     }
 }
 
-impl<T> fmt::Debug for SendError<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> fmt::Debug for SendError<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SendError<{}>(_)", stringify!(T))
     }
 }
 
 #[cfg(feature = "std")]
-impl<T> std::error::Error for SendError<T> {}
+impl<T, A: Allocator> std::error::Error for SendError<T, A> {}
+
+/// An error returned when waiting on a [`Delivery`](crate::Delivery) handle, if the [`Receiver`]
+/// was dropped without taking the message out of the channel. Contains the message.
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Dropped<T>(pub T);
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+impl<T> fmt::Display for Dropped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "receiver dropped without taking the message".fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug> std::error::Error for Dropped<T> {}
 
 /// An error returned from the indefinitely blocking recv functions on a [`Receiver`].
 ///
 /// The recv operation can only fail if the corresponding [`Sender`] was dropped before sending
 /// any message. Or if a message has already been sent and received on the channel.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-pub struct RecvError;
+pub enum RecvError {
+    /// The sender was dropped before sending any message, or the message has already been
+    /// extracted from the receiver.
+    SenderDropped,
+
+    /// The sender was dropped before sending any message, while its thread was panicking.
+    SenderPanicked,
+}
 
 impl fmt::Display for RecvError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        "receiving on a closed channel".fmt(f)
+        let msg = match self {
+            RecvError::SenderDropped => "receiving on a closed channel",
+            RecvError::SenderPanicked => "receiving on a channel whose sender panicked",
+        };
+        msg.fmt(f)
     }
 }
 
@@ -140,16 +183,20 @@ pub enum TryRecvError {
     /// The channel is still open, but there was no message present in it.
     Empty,
 
-    /// The channel is closed. Either the sender was dropped before sending any message, or the
-    /// message has already been extracted from the receiver.
-    Disconnected,
+    /// The sender was dropped before sending any message, or the message has already been
+    /// extracted from the receiver.
+    SenderDropped,
+
+    /// The sender was dropped before sending any message, while its thread was panicking.
+    SenderPanicked,
 }
 
 impl fmt::Display for TryRecvError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match self {
             TryRecvError::Empty => "receiving on an empty channel",
-            TryRecvError::Disconnected => "receiving on a closed channel",
+            TryRecvError::SenderDropped => "receiving on a closed channel",
+            TryRecvError::SenderPanicked => "receiving on a channel whose sender panicked",
         };
         msg.fmt(f)
     }
@@ -164,16 +211,33 @@ pub enum RecvTimeoutError {
     /// No message arrived on the channel before the timeout was reached. The channel is still open.
     Timeout,
 
-    /// The channel is closed. Either the sender was dropped before sending any message, or the
-    /// message has already been extracted from the receiver.
-    Disconnected,
+    /// The sender was dropped before sending any message, or the message has already been
+    /// extracted from the receiver.
+    SenderDropped,
+
+    /// The sender was dropped before sending any message, while its thread was panicking.
+    SenderPanicked,
+}
+
+impl RecvTimeoutError {
+    /// Turns the error of a receive without a deadline, which can't time out, into a
+    /// [`RecvError`].
+    #[cfg(feature = "std")]
+    pub(crate) fn into_recv_error(self) -> RecvError {
+        match self {
+            RecvTimeoutError::SenderDropped => RecvError::SenderDropped,
+            RecvTimeoutError::SenderPanicked => RecvError::SenderPanicked,
+            RecvTimeoutError::Timeout => unreachable!("receive without a deadline timed out"),
+        }
+    }
 }
 
 impl fmt::Display for RecvTimeoutError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let msg = match self {
             RecvTimeoutError::Timeout => "timed out waiting on channel",
-            RecvTimeoutError::Disconnected => "channel is empty and sending half is closed",
+            RecvTimeoutError::SenderDropped => "channel is empty and sending half is closed",
+            RecvTimeoutError::SenderPanicked => "receiving on a channel whose sender panicked",
         };
         msg.fmt(f)
     }
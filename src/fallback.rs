@@ -0,0 +1,121 @@
+use super::{Allocator, Global, SendError, Sender};
+use core::fmt;
+use core::mem::ManuallyDrop;
+
+/// A [`Sender`] that sends a fallback message if it is dropped without sending anything.
+/// Created by [`Sender::or_on_drop`] and [`Sender::or_on_drop_with`].
+///
+/// The fallback is sent on every path that drops the sender, including early returns and
+/// unwinding from a panic. The receiver can't tell it apart from a message sent with
+/// [`FallbackSender::send`]. If it must be able to, use a message type that says so, like an
+/// enum or a `Result`.
+///
+/// The fallback is kept in this struct, next to the sender. Sending it on drop uses the same
+/// channel allocation as any other send.
+///
+/// ```rust
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// fn handle(request: u32, reply: oneshot::Sender<Result<u32, &'static str>>) {
+///     let reply = reply.or_on_drop(Err("request dropped"));
+///     if request == 0 {
+///         // Early return without answering. The receiver gets the fallback.
+///         return;
+///     }
+///     let _ = reply.send(Ok(request * 2));
+/// }
+///
+/// let (sender, receiver) = oneshot::channel();
+/// handle(0, sender);
+/// assert_eq!(receiver.recv(), Ok(Err("request dropped")));
+/// # }
+/// ```
+pub struct FallbackSender<T, F = fn() -> T, A: Allocator = Global>
+where
+    F: FnOnce() -> T,
+{
+    sender: ManuallyDrop<Sender<T, A>>,
+    fallback: ManuallyDrop<Fallback<T, F>>,
+}
+
+enum Fallback<T, F> {
+    Value(T),
+    With(F),
+}
+
+impl<T, A: Allocator> Sender<T, A> {
+    /// Wraps the sender so `value` is sent to the [`Receiver`](crate::Receiver) if the sender is
+    /// dropped without sending anything. See [`FallbackSender`].
+    pub fn or_on_drop(self, value: T) -> FallbackSender<T, fn() -> T, A> {
+        FallbackSender {
+            sender: ManuallyDrop::new(self),
+            fallback: ManuallyDrop::new(Fallback::Value(value)),
+        }
+    }
+
+    /// Like [`Sender::or_on_drop`], but the fallback message is only created, by calling
+    /// `fallback`, if the sender is dropped without sending anything.
+    ///
+    /// If the sender is dropped during a panic, `fallback` is called while unwinding. A panic
+    /// in it then aborts the process.
+    pub fn or_on_drop_with<F: FnOnce() -> T>(self, fallback: F) -> FallbackSender<T, F, A> {
+        FallbackSender {
+            sender: ManuallyDrop::new(self),
+            fallback: ManuallyDrop::new(Fallback::With(fallback)),
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T, A: Allocator> FallbackSender<T, F, A> {
+    /// Sends `message` instead of the fallback, exactly like [`Sender::send`]. The fallback is
+    /// dropped without being sent.
+    pub fn send(self, message: T) -> Result<(), SendError<T, A>> {
+        self.into_sender().send(message)
+    }
+
+    /// Returns true if the associated [`Receiver`](crate::Receiver) has been dropped. See
+    /// [`Sender::is_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Drops the fallback without sending it, and returns the plain [`Sender`].
+    pub fn into_sender(self) -> Sender<T, A> {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used again, and its Drop implementation does not run.
+        unsafe {
+            ManuallyDrop::drop(&mut this.fallback);
+            ManuallyDrop::take(&mut this.sender)
+        }
+    }
+}
+
+impl<T, F: FnOnce() -> T, A: Allocator> Drop for FallbackSender<T, F, A> {
+    fn drop(&mut self) {
+        // SAFETY: Both fields are only taken here, and in `into_sender` which skips this.
+        let (sender, fallback) = unsafe {
+            (
+                ManuallyDrop::take(&mut self.sender),
+                ManuallyDrop::take(&mut self.fallback),
+            )
+        };
+        // If creating the message panics, the sender is dropped like any other one.
+        let message = match fallback {
+            Fallback::Value(value) => value,
+            Fallback::With(fallback) => fallback(),
+        };
+        // The receiver is already gone if this fails. The error drops the message and frees the
+        // channel, just like dropping the sender would have.
+        let _ = sender.send(message);
+    }
+}
+
+impl<T, F: FnOnce() -> T, A: Allocator> fmt::Debug for FallbackSender<T, F, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FallbackSender")
+            .field("state", &self.sender.state())
+            .finish_non_exhaustive()
+    }
+}
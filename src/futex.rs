@@ -0,0 +1,80 @@
+//! Blocking the receiving thread with the Linux futex syscall, directly on the channel state.
+//!
+//! A receiver blocking this way does not need a `Thread` handle in the channel, and waking it up
+//! is a single syscall, without the mutex involved in `Thread::unpark`.
+
+use super::states::State;
+use core::ptr;
+use core::sync::atomic::AtomicU32;
+use std::io;
+use std::time::Instant;
+
+/// A point in time on the `CLOCK_MONOTONIC` clock, the clock `Instant` is based on on Linux.
+/// The futex syscall takes it as an absolute timeout, so a wait interrupted by a spurious wakeup
+/// can be resumed without computing a new timeout.
+pub struct Deadline(libc::timespec);
+
+impl Deadline {
+    /// Converts `deadline`. Returns `None` if it is too far in the future to be represented, in
+    /// which case waiting without a timeout is equivalent.
+    pub fn new(deadline: Instant) -> Option<Self> {
+        let mut now = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: `now` is a valid timespec to write to. CLOCK_MONOTONIC is always supported.
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        // Both parts are below one second, so the sum can't overflow.
+        let mut nanos = now.tv_nsec as u32 + timeout.subsec_nanos();
+        let mut carry = 0;
+        if nanos >= 1_000_000_000 {
+            nanos -= 1_000_000_000;
+            carry = 1;
+        }
+        let secs = libc::time_t::try_from(timeout.as_secs())
+            .ok()?
+            .checked_add(now.tv_sec)?
+            .checked_add(carry)?;
+        Some(Self(libc::timespec {
+            tv_sec: secs,
+            tv_nsec: nanos as _,
+        }))
+    }
+}
+
+/// Blocks the current thread while `state` holds `expected`, until another thread calls `wake`
+/// on it or `deadline` passes. Can return spuriously, and returns immediately if `state` does
+/// not hold `expected`. Returns false only if the deadline passed.
+pub fn wait(state: &AtomicU32, expected: State, deadline: Option<&Deadline>) -> bool {
+    let timeout = deadline.map_or(ptr::null(), |deadline| &deadline.0 as *const libc::timespec);
+    // SAFETY: `state` is a valid, aligned 32 bit atomic for the duration of the call. The
+    // timeout is null or points to a valid timespec. FUTEX_WAIT_BITSET takes an absolute
+    // timeout on the CLOCK_MONOTONIC clock, and a bitset matching any wake.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            state as *const AtomicU32,
+            libc::FUTEX_WAIT_BITSET | libc::FUTEX_PRIVATE_FLAG,
+            expected,
+            timeout,
+            ptr::null::<u32>(),
+            !0u32,
+        )
+    };
+    result == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ETIMEDOUT)
+}
+
+/// Wakes up the thread blocked in `wait` on `state`, if there is one.
+pub fn wake(state: &AtomicU32) {
+    // SAFETY: `state` is a valid, aligned 32 bit atomic for the duration of the call.
+    unsafe {
+        libc::syscall(
+            libc::SYS_futex,
+            state as *const AtomicU32,
+            libc::FUTEX_WAKE | libc::FUTEX_PRIVATE_FLAG,
+            1,
+        )
+    };
+}
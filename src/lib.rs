@@ -66,7 +66,7 @@
 //!     match receiver2.recv_timeout(Duration::from_secs(1)) {
 //!         Ok(result) => break result,
 //!         Err(oneshot::RecvTimeoutError::Timeout) => println!("Still working..."),
-//!         Err(oneshot::RecvTimeoutError::Disconnected) => panic!("Worker thread died"),
+//!         Err(oneshot::RecvTimeoutError::SenderDropped) => panic!("Worker thread died"),
 //!     }
 //! };
 //! println!("Done computing. Results: {:?}", result);
@@ -74,6 +74,14 @@
 //! # }
 //! ```
 //!
+//! To wait for the first of many channels, use [`select`] or one of its time limited variants.
+//! To wait for all of them with a shared deadline, use [`gather`].
+//!
+//! When many threads or tasks wait for the same value, use a [`shared_channel`]. Its
+//! [`SharedReceiver`] can be cloned, and every clone gets a clone of the message. When many
+//! threads or tasks race to produce the value, use a [`race_channel`]. Its [`RaceSender`] can be
+//! cloned, and the first one to send wins.
+//!
 //! # Sync vs async
 //!
 //! The main motivation for writing this library was that there were no (known to me) channel
@@ -89,6 +97,36 @@
 //! in an asynchronous task. This implementation is completely executor/runtime agnostic. It should
 //! be possible to use this library with any executor.
 //!
+//! Every other async operation is a method returning a named future, like [`Sender::closed`]
+//! and [`Receiver::ready`]. The receiver is only a future itself because receiving consumes it.
+//! Where the blocking version of an operation already has the plain name, the async method gets
+//! an `_async` suffix. For example `send_async` and `recv_async` on the channels carrying more
+//! than one message, next to their blocking `send` and `recv`. The oneshot endpoints also have
+//! `poll_closed` and `poll_ready`, for use in hand written futures.
+//!
+//! # More than one message
+//!
+//! With the `std` feature, the crate also has channels carrying more than one message, with the
+//! same mix of blocking and async operations. They keep their state behind a mutex, and blocked
+//! threads and tasks wait in the same queues.
+//!
+//! * [`rendezvous`]: A channel without capacity, where a send completes once a receiver has
+//!   taken the message.
+//! * [`bounded`]: An mpmc queue holding up to a fixed number of messages.
+//! * [`unbounded`]: An mpmc queue without a limit, where sending never waits for room.
+//! * [`broadcast`]: A ring buffer where every receiver observes every message.
+//!
+//! # Without a heap
+//!
+//! All channels created by [`channel`] are allocated on the heap. For targets without a heap,
+//! disable the default features and use a [`StaticChannel`] instead. It keeps the channel inline,
+//! for example in a `static`, and hands out endpoints borrowing it.
+//!
+//! Without the `std` feature there are no thread blocking receive methods. The `park` feature
+//! adds [`Receiver::recv_with`] and [`Receiver::recv_deadline_with`], which block through a
+//! [`Park`] implementation and measure time with a [`Clock`] implementation of your choice. See
+//! the [`park`] module.
+//!
 //! # Footnotes
 //!
 //! [1]: See documentation on [Sender::send] for situations where it might not be fully wait-free.
@@ -97,24 +135,53 @@
 //
 // When a channel is created via the channel function, it allocates space on the heap to fit:
 // * A one byte atomic integer that represents the current channel state,
+// * A one byte atomic integer that represents the state of the sender waiting for the receiver
+//   to go away,
+// * A one byte atomic boolean that tells whether the receiver waits in a task or blocks a thread,
+//   only used to report the channel state,
+// * A one byte atomic integer that represents the origin of the channel memory. It tells the last
+//   endpoint whether to free the memory, hand it back to a ReusableChannel or ChannelPool, or
+//   leave it to the race senders of a race channel,
 // * Uninitialized memory to fit the message,
 // * Uninitialized memory to fit the waker that can wake the receiving task or thread up.
+// * Uninitialized memory to fit the waker that can wake the sending task or thread up, when it
+//   waits for the receiver to be dropped.
+// * The allocator the channel was allocated in. Takes zero bytes for the default global allocator.
+//
+// The size of a waker depends on which features are activated, it ranges from 0 to 24 bytes[1].
+// So with all features enabled (the default) each channel allocates two wakers and four bytes
+// plus the size of the message, plus any padding needed to get correct memory alignment.
 //
-// The size of the waker depends on which features are activated, it ranges from 0 to 24 bytes[1].
-// So with all features enabled (the default) each channel allocates 25 bytes plus the size of the
-// message, plus any padding needed to get correct memory alignment.
+// A StaticChannel holds the same channel object inline instead, and its endpoints borrow it. All
+// of the state machine described below is implemented on the channel object, and is shared by
+// both kinds of endpoints.
 //
 // The Sender and Receiver only holds a raw pointer to this heap channel object. The last endpoint
 // to be consumed or dropped is responsible for freeing the heap memory. The first endpoint to
 // go away signal via the state that it is gone. And the second one see this and frees the memory.
 //
 // Sending on the sender copies the message to the (so far uninitialized) memory region on the
-// heap and swaps the state from whatever it was to MESSAGE.
-// if the state before the swap was DISCONNECTED the SendError is returned and nothing else is done.
+// heap and moves the state to MESSAGE with a single atomic add.
+// if the state before the add was DISCONNECTED the SendError is returned and nothing else is done.
 // The SendError now owns the heap channel memory and is responsible for dropping the message
 // and freeing the memory.
-// If the state was RECEIVING the sender reads the waker object from the channel heap memory and
-// call the unpark method, which will wake up the receiver.
+// If the state was RECEIVING the add moved it to UNPARKING. The sender reads the waker object from
+// the channel heap memory, sets the state to MESSAGE and calls the unpark method on the waker,
+// which will wake up the receiver. The receiver never frees the channel, nor touches the waker,
+// while in the UNPARKING state. This allows the sender to safely read the waker.
+//
+// The sender can wait for the receiver to go away by writing its own waker to the channel and
+// changing the sender state from IDLE to WAITING. When the receiver is dropped it swaps the sender
+// state to NOTIFIED *before* changing the channel state, and takes the sender waker if there was
+// one. This way the receiver never touches the channel after the sender might have freed it.
+//
+// A message sent with Sender::send_acked keeps a Delivery handle alive on the sender side. It
+// reuses the sender state and sender waker, which are not needed by the sender after sending.
+// The sender state is set to ACKING before the message becomes visible, also if the receiver is
+// already going away. The receiver checks for it after disconnecting the channel. When it takes
+// the message or goes away, it reports this through the sender state and wakes the handle up.
+// The handle counts as an endpoint, so the channel is only released when both it and the
+// receiver are gone. A receiver going away without taking the message leaves it for the handle.
 //
 // Receiving on the channel first checks the state. If it is MESSAGE the message object is read
 // from the heap back into the stack, the heap memory is freed and the message returned. If the
@@ -123,100 +190,273 @@
 // the channel on the heap and does an atomic compare_and_swap on the state from EMPTY to RECEIVING.
 // If the swap went fine, it either parks the thread or returns Poll::Pending, depending on if
 // the receive is a blocking or an async one. It now just waits for the sender to wake it up.
+// A receiver that wants to replace its waker, for example when it is polled again or when a
+// blocking receive is started after it has been polled, first moves the state from RECEIVING back
+// to EMPTY with a compare_and_swap. If that fails the sender has already started waking it up.
+//
+// On Linux, a blocking receive does not park the thread. It writes a waker without any payload
+// and blocks in a futex wait directly on the state word, which is 32 bits wide there for this
+// reason. The sender wakes it with a futex wake on the state word *before* setting the final
+// state, since the receiver might free the channel right after that. A receiver woken up while
+// the state is still UNPARKING spins until the sender has set the final state.
+//
+// ## Memory orderings
+//
+// The atomic operations on the channel state and sender state use the weakest ordering that
+// keeps the following rules intact:
+// * Writing the message, or a waker, happens before the state change that hands it over to the
+//   other side. Those state changes are Release, and the loads or read-modify-writes observing
+//   them are Acquire.
+// * An endpoint about to free the channel must have observed, with Acquire, a Release operation
+//   done by the other endpoint after its last access to the channel. So the read-modify-writes
+//   that disconnect the channel are AcqRel.
+// * Transitions that hand nothing over are Relaxed. For example the receiver marking the channel
+//   DISCONNECTED after taking the message out, since only the receiver itself looks at the state
+//   after that. Or an endpoint taking back its own waker, it owns the waker already.
+// * Every decision is made on the result of a read-modify-write, never on a plain load alone.
+//   Plain loads are only hints, or happen after a park that was ended by an unpark, which
+//   synchronizes. No operation needs to be SeqCst, since no decision depends on the order of
+//   operations on two different atomics.
+// The `origin` field is not part of the state machine. It is only touched when the channel is
+// freed or reused, and stays SeqCst.
+// Rust 1.63 requires the failure ordering of a compare_exchange to be no stronger than the
+// success ordering. Some compare_exchange calls are therefore stronger on success than needed.
 //
 //
 // ## Footnotes
 //
-// [1]: Mind that the waker only takes zero bytes when all features are disabled, making it
-//      impossible to *wait* for the message. `try_recv` the only available method in this scenario.
+// [1]: Mind that the waker only takes zero bytes when none of the `std`, `park` and `async`
+//      features are enabled, making it impossible to *wait* for the message. `try_recv` the only
+//      available method in this scenario.
 
 #![deny(rust_2018_idioms)]
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
 
-#[cfg(not(loom))]
+#[cfg(feature = "alloc")]
 extern crate alloc;
 
-use core::{
-    marker::PhantomData,
-    mem::{self, MaybeUninit},
-    ptr::{self, NonNull},
-};
+#[cfg(feature = "alloc")]
+use core::{fmt, marker::PhantomData, mem, ptr::NonNull};
+use core::{mem::MaybeUninit, ptr};
 
+#[cfg(not(any(loom, oneshot_seqcst)))]
+use core::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed};
 #[cfg(not(loom))]
-use core::{
-    cell::UnsafeCell,
-    sync::atomic::{AtomicU8, Ordering::SeqCst},
-};
+use core::{cell::UnsafeCell, sync::atomic::AtomicU8};
+// Building with `--cfg oneshot_seqcst` makes every channel state ordering SeqCst again. It is
+// only meant as the baseline when benchmarking the orderings.
+#[cfg(all(oneshot_seqcst, not(loom)))]
+use core::sync::atomic::Ordering::{SeqCst as AcqRel, SeqCst as Acquire, SeqCst as Relaxed};
 #[cfg(loom)]
 use loom::{
     cell::UnsafeCell,
-    sync::atomic::{AtomicU8, Ordering::SeqCst},
+    sync::atomic::{
+        AtomicU8,
+        Ordering::{AcqRel, Acquire, Relaxed},
+    },
 };
 
+// The futex syscall only operates on 32 bit words.
+#[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+use core::sync::atomic::AtomicU32 as AtomicState;
+#[cfg(not(any(all(target_os = "linux", feature = "std"), loom)))]
+use core::sync::atomic::AtomicU8 as AtomicState;
+#[cfg(loom)]
+use loom::sync::atomic::AtomicU8 as AtomicState;
+
+#[cfg(all(any(feature = "park", feature = "async"), not(loom)))]
+use core::sync::atomic::AtomicBool;
+#[cfg(all(any(feature = "park", feature = "async"), loom))]
+use loom::sync::atomic::AtomicBool;
+
+#[cfg(all(
+    any(feature = "park", feature = "async"),
+    not(any(loom, oneshot_seqcst))
+))]
+use core::sync::atomic::Ordering::Release;
+#[cfg(all(any(feature = "park", feature = "async"), oneshot_seqcst, not(loom)))]
+use core::sync::atomic::Ordering::SeqCst as Release;
+#[cfg(all(feature = "alloc", not(loom)))]
+use core::sync::atomic::Ordering::SeqCst;
+#[cfg(all(any(feature = "park", feature = "async"), loom))]
+use loom::sync::atomic::Ordering::Release;
+#[cfg(all(feature = "alloc", loom))]
+use loom::sync::atomic::Ordering::SeqCst;
+
+#[cfg(all(any(feature = "park", feature = "async"), not(loom)))]
+use core::hint;
+#[cfg(all(any(feature = "park", feature = "async"), loom))]
+use loom::hint;
+
+#[cfg(all(feature = "alloc", feature = "async"))]
+use core::pin::Pin;
+#[cfg(any(feature = "park", feature = "async"))]
+use core::task;
 #[cfg(feature = "async")]
-use core::{
-    pin::Pin,
-    task::{self, Poll},
-};
+use core::task::Poll;
 #[cfg(feature = "std")]
 use std::time::{Duration, Instant};
 
 #[cfg(feature = "std")]
 mod thread {
     #[cfg(not(loom))]
-    pub use std::thread::{current, park, park_timeout, Thread};
+    pub use std::thread::{current, park, park_timeout, yield_now, Thread};
 
     #[cfg(loom)]
-    pub use loom::thread::{current, park, Thread};
+    pub use loom::thread::{current, park, yield_now, Thread};
 
     // loom does not support parking with a timeout. So we just
     // yield. This means that the "park" will "spuriously" wake up
     // way too early. But the code should properly handle this.
     // One thing to note is that very short timeouts are needed
     // when using loom, since otherwise the looping will cause
-    // an overflow in loom.
+    // an overflow in loom. We also sleep for a short while in real time,
+    // so the number of loop iterations before the deadline does not depend
+    // on how fast the machine happens to run the model.
     #[cfg(loom)]
-    pub fn park_timeout(_timeout: std::time::Duration) {
+    pub fn park_timeout(timeout: std::time::Duration) {
+        std::thread::sleep(timeout.min(std::time::Duration::from_micros(50)));
         loom::thread::yield_now()
     }
 }
 
-#[cfg(loom)]
+#[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+mod futex;
+#[cfg(all(loom, feature = "alloc"))]
 mod loombox;
-#[cfg(not(loom))]
-use alloc::boxed::Box;
-#[cfg(loom)]
-use loombox::Box;
+#[cfg(feature = "alloc")]
+use alloc::alloc::handle_alloc_error;
+#[cfg(feature = "alloc")]
+use allocator_api2::alloc::{Allocator, Global};
+#[cfg(feature = "alloc")]
+use core::alloc::Layout;
+
+mod channel_state;
+pub use channel_state::ChannelState;
 
 mod errors;
-pub use errors::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+pub use errors::Dropped;
+#[cfg(feature = "alloc")]
+pub use errors::SendError;
+pub use errors::{RecvError, RecvTimeoutError, TryRecvError};
+
+#[cfg(feature = "alloc")]
+mod fallback;
+#[cfg(feature = "alloc")]
+pub use fallback::FallbackSender;
+
+#[cfg(feature = "alloc")]
+mod pool;
+#[cfg(feature = "alloc")]
+pub use pool::ChannelPool;
+
+#[cfg(feature = "alloc")]
+mod race;
+#[cfg(feature = "alloc")]
+pub use race::{race_channel, RaceSendError, RaceSender};
+
+#[cfg(feature = "alloc")]
+mod reusable;
+#[cfg(feature = "alloc")]
+pub use reusable::ReusableChannel;
+
+#[cfg(feature = "std")]
+mod select;
+#[cfg(feature = "std")]
+pub use select::{gather, select, select_deadline, select_timeout};
+
+#[cfg(feature = "park")]
+pub mod park;
+#[cfg(feature = "park")]
+pub use park::{Clock, Park, Unpark};
+
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "std")]
+pub use shared::{shared_channel, SharedReceiver};
+
+#[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+pub mod pollable;
+#[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+pub use pollable::PollableReceiver;
+
+#[cfg(feature = "std")]
+mod strategy;
+#[cfg(feature = "std")]
+pub use strategy::RecvStrategy;
+
+pub mod static_channel;
+pub use static_channel::StaticChannel;
+
+#[cfg(feature = "std")]
+mod recv_errors;
+#[cfg(feature = "std")]
+mod send_errors;
+#[cfg(feature = "std")]
+mod waiters;
+
+#[cfg(feature = "std")]
+pub mod bounded;
+#[cfg(feature = "std")]
+pub mod broadcast;
+#[cfg(feature = "std")]
+pub mod rendezvous;
+#[cfg(feature = "std")]
+pub mod unbounded;
 
 /// Creates a new oneshot channel and returns the two endpoints, [`Sender`] and [`Receiver`].
+#[cfg(feature = "alloc")]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    channel_in(Global)
+}
+
+/// Creates a new oneshot channel in the given allocator and returns the two endpoints,
+/// [`Sender`] and [`Receiver`].
+///
+/// The allocator has to implement the `Allocator` trait from the [`allocator-api2`] crate.
+/// It is moved into the channel allocation, and is used to free the channel again when both
+/// endpoints are gone. This can happen on either endpoint's thread.
+///
+/// [`allocator-api2`]: https://docs.rs/allocator-api2
+#[cfg(feature = "alloc")]
+pub fn channel_in<T, A: Allocator>(alloc: A) -> (Sender<T, A>, Receiver<T, A>) {
     // Allocate the channel on the heap and get the pointer.
     // The last endpoint of the channel to be alive is responsible for freeing the channel
     // and dropping any object that might have been written to it.
+    let channel_ptr = allocate(Channel::new(HEAP), alloc);
 
-    let channel_ptr = Box::into_raw(Box::new(Channel::new()));
-
-    // SAFETY: `channel_ptr` came from a Box and thus is not null
-    let channel_ptr = unsafe { NonNull::new_unchecked(channel_ptr) };
+    // SAFETY: The channel was just created, nothing else uses it.
+    unsafe { endpoints(channel_ptr) }
+}
 
+/// Creates the two endpoints of the channel at `channel_ptr`.
+///
+/// # Safety
+///
+/// The channel must be in its initial state, and no other endpoint may be using it.
+#[cfg(feature = "alloc")]
+unsafe fn endpoints<T, A: Allocator>(
+    channel_ptr: NonNull<Channel<T>>,
+) -> (Sender<T, A>, Receiver<T, A>) {
     (
         Sender {
             channel_ptr,
             _invariant: PhantomData,
             _dropck: PhantomData,
+            _alloc: PhantomData,
         },
         Receiver {
             channel_ptr,
             _dropck: PhantomData,
+            _alloc: PhantomData,
         },
     )
 }
 
-#[derive(Debug)]
-pub struct Sender<T> {
+#[cfg(feature = "alloc")]
+pub struct Sender<T, A: Allocator = Global> {
     channel_ptr: NonNull<Channel<T>>,
     // In reality we want contravariance, however we can't obtain that.
     //
@@ -237,22 +477,32 @@ pub struct Sender<T> {
     _invariant: PhantomData<fn(T) -> T>,
     // See SendError for details
     _dropck: PhantomData<T>,
+    // The allocator lives in the channel allocation, see `ChannelBlock`
+    _alloc: PhantomData<A>,
 }
 
-#[derive(Debug)]
-pub struct Receiver<T> {
+#[cfg(feature = "alloc")]
+pub struct Receiver<T, A: Allocator = Global> {
     // Covariance is the right choice here. Consider the example presented in Sender, and you'll
     // see that if we replaced `rx` instead then we would get the expected behavior
     channel_ptr: NonNull<Channel<T>>,
     // See SendError for details
     _dropck: PhantomData<T>,
+    // The allocator lives in the channel allocation, see `ChannelBlock`
+    _alloc: PhantomData<A>,
 }
 
-unsafe impl<T: Send> Send for Sender<T> {}
-unsafe impl<T: Send> Send for Receiver<T> {}
-impl<T> Unpin for Receiver<T> {}
-
-impl<T> Sender<T> {
+// The allocator is only ever used by the last endpoint, to free the channel. It is never
+// used from two threads at once, so it does not have to be `Sync`.
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send, A: Allocator + Send> Send for Sender<T, A> {}
+#[cfg(feature = "alloc")]
+unsafe impl<T: Send, A: Allocator + Send> Send for Receiver<T, A> {}
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Unpin for Receiver<T, A> {}
+
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Sender<T, A> {
     /// Sends `message` over the channel to the corresponding [`Receiver`].
     ///
     /// Returns an error if the receiver has already been dropped. The message can
@@ -260,64 +510,152 @@ impl<T> Sender<T> {
     ///
     /// This method is completely lock-free and wait-free when sending on a channel that the
     /// receiver is currently not receiving on. If the receiver is receiving during the send
-    /// operation this method includes waking up the thread/task. Unparking a thread currently
-    /// involves a mutex in Rust's standard library. How lock-free waking up an async task is
-    /// depends on your executor. If this method returns a `SendError`, please mind that dropping
-    /// the error involves running any drop implementation on the message type, which might or
-    /// might not be lock-free.
-    pub fn send(self, message: T) -> Result<(), SendError<T>> {
+    /// operation this method includes waking up the thread/task. On Linux a receiver blocked in
+    /// a thread blocking receive method is woken up with a single futex syscall. On other
+    /// platforms unparking a thread currently involves a mutex in Rust's standard library. How
+    /// lock-free waking up an async task is depends on your executor. If this method returns a
+    /// `SendError`, please mind that dropping the error involves running any drop implementation
+    /// on the message type, which might or might not be lock-free.
+    pub fn send(self, message: T) -> Result<(), SendError<T, A>> {
         let channel_ptr = self.channel_ptr;
 
         // Don't run our Drop implementation if send was called, any cleanup now happens here
         mem::forget(self);
 
-        let channel = unsafe { channel_ptr.as_ref() };
+        match unsafe { channel_ptr.as_ref().send(message) } {
+            Ok(()) => Ok(()),
+            // The receiver was already dropped. The error is responsible for freeing the channel.
+            Err(()) => Err(unsafe { SendError::new(channel_ptr) }),
+        }
+    }
 
-        // Write the message into the channel on the heap.
-        unsafe { channel.write_message(message) };
+    /// Sends `message` like [`Sender::send`], but returns a [`Delivery`] handle that tells when
+    /// the [`Receiver`] has taken the message out of the channel.
+    ///
+    /// If the receiver is dropped without taking the message, the message is not dropped with it.
+    /// It is instead handed back through the [`Delivery`] handle, as a [`Dropped`] error.
+    ///
+    /// Returns an error if the receiver has already been dropped, just like [`Sender::send`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let (sender, receiver) = oneshot::channel();
+    /// let delivery = sender.send_acked("hello").unwrap();
+    /// drop(receiver);
+    /// assert_eq!(delivery.wait(), Err(oneshot::Dropped("hello")));
+    /// ```
+    #[cfg(any(feature = "std", feature = "async"))]
+    pub fn send_acked(self, message: T) -> Result<Delivery<T, A>, SendError<T, A>> {
+        let channel_ptr = self.channel_ptr;
 
-        // Set the state to signal there is a message on the channel.
-        match channel.state.swap(MESSAGE, SeqCst) {
-            // The receiver is alive and has not started waiting. Send done.
-            EMPTY => Ok(()),
-            // The receiver is waiting. Wake it up so it can return the message.
-            RECEIVING => {
-                unsafe { channel.take_waker() }.unpark();
-                Ok(())
-            }
+        // Don't run our Drop implementation if send was called, any cleanup now happens here
+        mem::forget(self);
+
+        match unsafe { channel_ptr.as_ref().send_acked(message) } {
+            Ok(()) => Ok(Delivery {
+                channel_ptr,
+                _dropck: PhantomData,
+                _alloc: PhantomData,
+            }),
             // The receiver was already dropped. The error is responsible for freeing the channel.
-            DISCONNECTED => Err(unsafe { SendError::new(channel_ptr) }),
-            _ => unreachable!(),
+            Err(()) => Err(unsafe { SendError::new(channel_ptr) }),
         }
     }
+
+    /// Returns true if the associated [`Receiver`] has been dropped.
+    ///
+    /// If this returns true, any future call to [`Sender::send`] is guaranteed to return an error.
+    /// If it returns false, the receiver might still be dropped before the message is sent.
+    ///
+    /// This method is completely lock-free and wait-free. It only does a single atomic integer
+    /// load of the channel state.
+    pub fn is_closed(&self) -> bool {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref() }.is_closed()
+    }
+
+    /// Returns a snapshot of the channel state. Useful for debugging, or for checking if the
+    /// [`Receiver`] is currently waiting for the message. See [`ChannelState`].
+    ///
+    /// Since the sender is alive, the state is [`Disconnected`](ChannelState::Disconnected) only
+    /// if the receiver has been dropped.
+    pub fn state(&self) -> ChannelState {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref() }.channel_state()
+    }
+
+    /// Blocks the current thread until the associated [`Receiver`] has been dropped.
+    ///
+    /// Useful for abandoning expensive work that produces the message if nobody is going to
+    /// receive it anyway.
+    #[cfg(feature = "std")]
+    pub fn wait_closed(&self) {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait_closed(None) };
+    }
+
+    /// Like [`Sender::wait_closed`], but will not block longer than `timeout`. Returns true
+    /// if the [`Receiver`] was dropped before the timeout was reached, false otherwise.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking wait.
+    #[cfg(feature = "std")]
+    pub fn wait_closed_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait_closed(deadline) }
+    }
+
+    /// Returns a future that completes when the associated [`Receiver`] has been dropped.
+    ///
+    /// This is the async version of [`Sender::wait_closed`]. Polling the returned future and
+    /// then blocking in [`Sender::wait_closed`], or the other way around, is supported.
+    #[cfg(feature = "async")]
+    pub fn closed(&mut self) -> Closed<'_, T, A> {
+        Closed { sender: self }
+    }
+
+    /// Polls whether the associated [`Receiver`] has been dropped. Returns `Poll::Ready(())` if it
+    /// has, otherwise registers the task in `cx` to be woken up when it happens.
+    #[cfg(feature = "async")]
+    pub fn poll_closed(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().poll_closed(cx) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> fmt::Debug for Sender<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
 }
 
-impl<T> Drop for Sender<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Drop for Sender<T, A> {
     fn drop(&mut self) {
         // SAFETY: The reference won't be used after the channel is freed in this method
         let channel = unsafe { self.channel_ptr.as_ref() };
 
-        // Set the channel state to disconnected and read what state the receiver was in
-        match channel.state.swap(DISCONNECTED, SeqCst) {
-            // The receiver has not started waiting, nor is it dropped.
-            EMPTY => (),
-            // The receiver is waiting. Wake it up so it can detect that the channel disconnected.
-            RECEIVING => unsafe { channel.take_waker() }.unpark(),
+        if unsafe { channel.drop_sender() } {
             // The receiver was already dropped. We are responsible for freeing the channel.
-            DISCONNECTED => {
-                unsafe { dealloc(self.channel_ptr) };
-            }
-            _ => unreachable!(),
+            unsafe { dealloc::<T, A>(self.channel_ptr) };
         }
     }
 }
 
-impl<T> Receiver<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Receiver<T, A> {
     /// Checks if there is a message in the channel without blocking. Returns:
     ///  * `Ok(message)` if there was a message in the channel.
     ///  * `Err(Empty)` if the [`Sender`] is alive, but has not yet sent a message.
-    ///  * `Err(Disconnected)` if the [`Sender`] was dropped before sending anything or if the
+    ///  * `Err(SenderDropped)` if the [`Sender`] was dropped before sending anything or if the
     ///    message has already been extracted by a previous receive call.
+    ///  * `Err(SenderPanicked)` if the [`Sender`] was dropped before sending anything, while its
+    ///    thread was panicking.
     ///
     /// If a message is returned, the channel is disconnected and any subsequent receive operation
     /// using this receiver will return an error.
@@ -328,23 +666,48 @@ impl<T> Receiver<T> {
     /// returning it.
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         // SAFETY: The channel will not be freed while this method is still running.
-        let channel = unsafe { self.channel_ptr.as_ref() };
+        unsafe { self.channel_ptr.as_ref().try_recv() }
+    }
 
-        match channel.state.load(SeqCst) {
-            // The sender is alive but has not sent anything yet.
-            EMPTY => Err(TryRecvError::Empty),
-            // The sender sent the message. We take the message and mark the channel disconnected.
-            MESSAGE => {
-                channel.state.store(DISCONNECTED, SeqCst);
-                Ok(unsafe { channel.take_message() })
-            }
-            // The sender was dropped before sending anything, or we already received the message.
-            DISCONNECTED => Err(TryRecvError::Disconnected),
-            // The receiver must have already been `Future::poll`ed. No message available.
-            #[cfg(feature = "async")]
-            RECEIVING => Err(TryRecvError::Empty),
-            _ => unreachable!(),
-        }
+    /// Returns a snapshot of the channel state. Useful for debugging. See [`ChannelState`].
+    ///
+    /// The receiver can't observe its own blocking receive, so the state is only
+    /// [`Receiving`](ChannelState::Receiving) if this receiver was polled asynchronously.
+    pub fn state(&self) -> ChannelState {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref() }.channel_state()
+    }
+
+    /// Returns true if there is a message in the channel. A following receive call is then
+    /// guaranteed to return it without blocking.
+    pub fn has_message(&self) -> bool {
+        self.state() == ChannelState::Message
+    }
+
+    /// Returns true if the channel is closed. That is if the [`Sender`] was dropped before
+    /// sending anything, or if the message has already been extracted by a previous receive
+    /// call. Any following receive call is then guaranteed to return an error.
+    pub fn is_closed(&self) -> bool {
+        self.state() == ChannelState::Disconnected
+    }
+
+    /// Closes the channel from the receiving side and returns the message, if one was sent and
+    /// not yet received.
+    ///
+    /// This works like dropping the receiver, except that a message already in the channel is
+    /// handed back instead of being dropped. It is done atomically: either the [`Sender`] sent
+    /// the message before this call, and it is returned here, or any later [`Sender::send`]
+    /// fails and returns the message in its [`SendError`].
+    ///
+    /// A message sent with [`Sender::send_acked`] counts as received when returned from here.
+    pub fn close(self) -> Option<T> {
+        let channel_ptr = self.channel_ptr;
+
+        // Don't run our Drop implementation, closing does everything it would do.
+        mem::forget(self);
+
+        // SAFETY: The channel is only freed through the release closure, as the last access.
+        unsafe { channel_ptr.as_ref().close(|| dealloc::<T, A>(channel_ptr)) }
     }
 
     /// Attempts to wait for a message from the [`Sender`], returning an error if the channel is
@@ -356,14 +719,14 @@ impl<T> Receiver<T> {
     ///
     /// If the corresponding [`Sender`] has disconnected (been dropped), or it disconnects while
     /// this call is blocking, this call will wake up and return `Err` to indicate that the message
-    /// can never be received on this channel.
+    /// can never be received on this channel. The error is [`RecvError::SenderPanicked`] if the
+    /// sender was dropped while its thread was panicking, and [`RecvError::SenderDropped`]
+    /// otherwise.
     ///
     /// If a sent message has already been extracted from this channel this method will return an
     /// error.
     ///
-    /// # Panics
-    ///
-    /// Panics if called after this receiver has been polled asynchronously.
+    /// It is fine to call this method after this receiver has been polled asynchronously.
     #[cfg(feature = "std")]
     pub fn recv(self) -> Result<T, RecvError> {
         let channel_ptr = self.channel_ptr;
@@ -371,75 +734,30 @@ impl<T> Receiver<T> {
         // Don't run our Drop implementation if we are receiving consuming ourselves.
         mem::forget(self);
 
-        let channel = unsafe { channel_ptr.as_ref() };
+        // The sender has either sent the message or been dropped. Unless a `Delivery` handle is
+        // still alive, we are the last endpoint and are responsible for freeing the channel.
+        unsafe {
+            let channel = channel_ptr.as_ref();
+            channel.recv(channel.wait(None), || dealloc::<T, A>(channel_ptr))
+        }
+    }
 
-        match channel.state.load(SeqCst) {
-            // The sender is alive but has not sent anything yet. We prepare to park.
-            EMPTY => {
-                // Conditionally add a delay here to help the tests trigger the edge cases where
-                // the sender manages to be dropped or send something before we are able to store
-                // our waker object in the channel.
-                #[cfg(oneshot_test_delay)]
-                std::thread::sleep(std::time::Duration::from_millis(10));
+    /// Like [`Receiver::recv`], but busy-waits for the [`Sender`] as described by `strategy`
+    /// before blocking the thread. Lowers the latency if the message usually arrives shortly
+    /// after starting to receive. See [`RecvStrategy`] for details.
+    ///
+    /// It is fine to call this method after this receiver has been polled asynchronously.
+    #[cfg(feature = "std")]
+    pub fn recv_with_strategy(self, strategy: RecvStrategy) -> Result<T, RecvError> {
+        let channel_ptr = self.channel_ptr;
 
-                // Write our waker instance to the channel.
-                unsafe { channel.write_waker(ReceiverWaker::current_thread()) };
+        // Don't run our Drop implementation if we are receiving consuming ourselves.
+        mem::forget(self);
 
-                match channel
-                    .state
-                    .compare_exchange(EMPTY, RECEIVING, SeqCst, SeqCst)
-                {
-                    // We stored our waker, now we park until the sender has changed the state
-                    Ok(EMPTY) => loop {
-                        thread::park();
-                        match channel.state.load(SeqCst) {
-                            // The sender sent the message while we were parked.
-                            MESSAGE => {
-                                let message = unsafe { channel.take_message() };
-                                unsafe { dealloc(channel_ptr) };
-                                break Ok(message);
-                            }
-                            // The sender was dropped while we were parked.
-                            DISCONNECTED => {
-                                unsafe { dealloc(channel_ptr) };
-                                break Err(RecvError);
-                            }
-                            // State did not change, spurious wakeup, park again.
-                            RECEIVING => (),
-                            _ => unreachable!(),
-                        }
-                    },
-                    // The sender sent the message while we prepared to park.
-                    Err(MESSAGE) => {
-                        unsafe { channel.drop_waker() };
-                        let message = unsafe { channel.take_message() };
-                        unsafe { dealloc(channel_ptr) };
-                        Ok(message)
-                    }
-                    // The sender was dropped before sending anything while we prepared to park.
-                    Err(DISCONNECTED) => {
-                        unsafe { channel.drop_waker() };
-                        unsafe { dealloc(channel_ptr) };
-                        Err(RecvError)
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            // The sender already sent the message.
-            MESSAGE => {
-                let message = unsafe { channel.take_message() };
-                unsafe { dealloc(channel_ptr) };
-                Ok(message)
-            }
-            // The sender was dropped before sending anything, or we already received the message.
-            DISCONNECTED => {
-                unsafe { dealloc(channel_ptr) };
-                Err(RecvError)
-            }
-            // The receiver must have been `Future::poll`ed prior to this call.
-            #[cfg(feature = "async")]
-            RECEIVING => panic!("{}", RECEIVER_USED_SYNC_AND_ASYNC_ERROR),
-            _ => unreachable!(),
+        // See `Receiver::recv`.
+        unsafe {
+            let channel = channel_ptr.as_ref();
+            channel.recv(channel.busy_wait(strategy), || dealloc::<T, A>(channel_ptr))
         }
     }
 
@@ -450,80 +768,25 @@ impl<T> Receiver<T> {
     /// If a message is returned, the channel is disconnected and any subsequent receive operation
     /// using this receiver will return an error.
     ///
-    /// # Panics
-    ///
-    /// Panics if called after this receiver has been polled asynchronously.
+    /// It is fine to call this method after this receiver has been polled asynchronously.
     #[cfg(feature = "std")]
     pub fn recv_ref(&self) -> Result<T, RecvError> {
-        let channel_ptr = self.channel_ptr;
-        let channel = unsafe { channel_ptr.as_ref() };
-
-        match channel.state.load(SeqCst) {
-            // The sender is alive but has not sent anything yet. We prepare to park.
-            EMPTY => {
-                // Conditionally add a delay here to help the tests trigger the edge cases where
-                // the sender manages to be dropped or send something before we are able to store
-                // our waker object in the channel.
-                #[cfg(oneshot_test_delay)]
-                std::thread::sleep(std::time::Duration::from_millis(10));
-
-                // Write our waker instance to the channel.
-                unsafe { channel.write_waker(ReceiverWaker::current_thread()) };
-
-                match channel
-                    .state
-                    .compare_exchange(EMPTY, RECEIVING, SeqCst, SeqCst)
-                {
-                    // We stored our waker, now we park until the sender has changed the state
-                    Ok(EMPTY) => loop {
-                        thread::park();
-                        match channel.state.load(SeqCst) {
-                            // The sender sent the message while we were parked.
-                            // We take the message and mark the channel disconnected.
-                            MESSAGE => {
-                                channel.state.store(DISCONNECTED, SeqCst);
-                                break Ok(unsafe { channel.take_message() });
-                            }
-                            // The sender was dropped while we were parked.
-                            DISCONNECTED => break Err(RecvError),
-                            // State did not change, spurious wakeup, park again.
-                            RECEIVING => (),
-                            _ => unreachable!(),
-                        }
-                    },
-                    // The sender sent the message while we prepared to park.
-                    Err(MESSAGE) => {
-                        channel.state.store(DISCONNECTED, SeqCst);
-                        unsafe { channel.drop_waker() };
-                        Ok(unsafe { channel.take_message() })
-                    }
-                    // The sender was dropped before sending anything while we prepared to park.
-                    Err(DISCONNECTED) => {
-                        unsafe { channel.drop_waker() };
-                        Err(RecvError)
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            // The sender sent the message. We take the message and mark the channel disconnected.
-            MESSAGE => {
-                channel.state.store(DISCONNECTED, SeqCst);
-                Ok(unsafe { channel.take_message() })
-            }
-            // The sender was dropped before sending anything, or we already received the message.
-            DISCONNECTED => Err(RecvError),
-            // The receiver must have been `Future::poll`ed prior to this call.
-            #[cfg(feature = "async")]
-            RECEIVING => panic!("{}", RECEIVER_USED_SYNC_AND_ASYNC_ERROR),
-            _ => unreachable!(),
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel
+                .recv_ref(channel.wait(None))
+                .map_err(RecvTimeoutError::into_recv_error)
         }
     }
 
     /// Like [`Receiver::recv`], but will not block longer than `timeout`. Returns:
     ///  * `Ok(message)` if there was a message in the channel before the timeout was reached.
     ///  * `Err(Timeout)` if no message arrived on the channel before the timeout was reached.
-    ///  * `Err(Disconnected)` if the sender was dropped before sending anything or if the message
+    ///  * `Err(SenderDropped)` if the sender was dropped before sending anything or if the message
     ///    has already been extracted by a previous receive call.
+    ///  * `Err(SenderPanicked)` if the sender was dropped before sending anything, while its
+    ///    thread was panicking.
     ///
     /// If a message is returned, the channel is disconnected and any subsequent receive operation
     /// using this receiver will return an error.
@@ -531,220 +794,918 @@ impl<T> Receiver<T> {
     /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
     /// in the future this falls back to an indefinitely blocking receive operation.
     ///
-    /// # Panics
-    ///
-    /// Panics if called after this receiver has been polled asynchronously.
+    /// It is fine to call this method after this receiver has been polled asynchronously.
     #[cfg(feature = "std")]
     pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
-        match Instant::now().checked_add(timeout) {
-            Some(deadline) => self.recv_deadline(deadline),
-            None => self.recv_ref().map_err(|_| RecvTimeoutError::Disconnected),
+        let deadline = Instant::now().checked_add(timeout);
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel.recv_ref(channel.wait(deadline))
         }
     }
 
     /// Like [`Receiver::recv`], but will not block longer than until `deadline`. Returns:
     ///  * `Ok(message)` if there was a message in the channel before the deadline was reached.
     ///  * `Err(Timeout)` if no message arrived on the channel before the deadline was reached.
-    ///  * `Err(Disconnected)` if the sender was dropped before sending anything or if the message
+    ///  * `Err(SenderDropped)` if the sender was dropped before sending anything or if the message
     ///    has already been extracted by a previous receive call.
+    ///  * `Err(SenderPanicked)` if the sender was dropped before sending anything, while its
+    ///    thread was panicking.
     ///
     /// If a message is returned, the channel is disconnected and any subsequent receive operation
     /// using this receiver will return an error.
     ///
-    /// # Panics
-    ///
-    /// Panics if called after this receiver has been polled asynchronously.
+    /// It is fine to call this method after this receiver has been polled asynchronously.
     #[cfg(feature = "std")]
     pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel.recv_ref(channel.wait(Some(deadline)))
+        }
+    }
+
+    /// Blocks the current thread until the channel is ready, without receiving anything. The
+    /// channel is ready once the [`Sender`] has sent the message or been dropped, or if the
+    /// message has already been extracted by a previous receive call.
+    ///
+    /// A message is left in the channel, for a later receive call like [`Receiver::try_recv`]
+    /// to take out without blocking.
+    ///
+    /// It is fine to call this method after this receiver has been polled asynchronously.
+    #[cfg(feature = "std")]
+    pub fn wait(&self) {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait(None) };
+    }
+
+    /// Like [`Receiver::wait`], but will not block longer than `timeout`. Returns true if the
+    /// channel became ready before the timeout was reached, false otherwise.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking wait.
+    #[cfg(feature = "std")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait(deadline) != EMPTY }
+    }
+
+    /// Like [`Receiver::wait`], but will not block longer than until `deadline`. Returns true
+    /// if the channel became ready before the deadline was reached, false otherwise.
+    #[cfg(feature = "std")]
+    pub fn wait_deadline(&self, deadline: Instant) -> bool {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait(Some(deadline)) != EMPTY }
+    }
+
+    /// Returns a future that completes when the channel is ready, without receiving anything.
+    ///
+    /// This is the async version of [`Receiver::wait`]. A message is left in the channel, for
+    /// a later receive call, or polling the receiver itself, to take out without waiting.
+    #[cfg(feature = "async")]
+    pub fn ready(&mut self) -> Ready<'_, T, A> {
+        Ready { receiver: self }
+    }
+
+    /// Polls whether the channel is ready, see [`Receiver::wait`]. Returns `Poll::Ready(())` if
+    /// it is, otherwise registers the task in `cx` to be woken up when it becomes ready.
+    ///
+    /// Like polling the receiver as a `Future`, only the waker of the latest poll is woken up.
+    #[cfg(feature = "async")]
+    pub fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().poll_ready(cx) }.map(|_| ())
+    }
+
+    /// Like [`Receiver::recv`], but blocks through `parker` instead of parking the thread with
+    /// the standard library. Available without the `std` feature, with the `park` feature.
+    /// See the [`park`] module for details.
+    ///
+    /// It is fine to call this method after this receiver has been polled asynchronously.
+    #[cfg(feature = "park")]
+    pub fn recv_with<P: Park>(self, parker: &P) -> Result<T, RecvError> {
         let channel_ptr = self.channel_ptr;
-        let channel = unsafe { channel_ptr.as_ref() };
 
-        match channel.state.load(SeqCst) {
-            // The sender is alive but has not sent anything yet. We prepare to park.
-            EMPTY => {
-                // Conditionally add a delay here to help the tests trigger the edge cases where
-                // the sender manages to be dropped or send something before we are able to store
-                // our waker object in the channel.
-                #[cfg(oneshot_test_delay)]
-                std::thread::sleep(std::time::Duration::from_millis(10));
+        // Don't run our Drop implementation if we are receiving consuming ourselves.
+        mem::forget(self);
 
-                // Write our thread instance to the channel.
-                unsafe { channel.write_waker(ReceiverWaker::current_thread()) };
+        // See `Receiver::recv`.
+        unsafe {
+            let channel = channel_ptr.as_ref();
+            channel.recv(channel.wait_with(parker), || dealloc::<T, A>(channel_ptr))
+        }
+    }
 
-                match channel
-                    .state
-                    .compare_exchange(EMPTY, RECEIVING, SeqCst, SeqCst)
-                {
-                    // We stored our waker, now we park until the sender has changed the state
-                    Ok(EMPTY) => loop {
-                        let (state, timed_out) = if let Some(timeout) =
-                            deadline.checked_duration_since(Instant::now())
-                        {
-                            thread::park_timeout(timeout);
-                            (channel.state.load(SeqCst), false)
-                        } else {
-                            // We reached the deadline. Stop being in the receiving state.
-                            (channel.state.swap(EMPTY, SeqCst), true)
-                        };
-                        match state {
-                            // The sender sent the message while we were parked.
-                            MESSAGE => {
-                                channel.state.store(DISCONNECTED, SeqCst);
-                                break Ok(unsafe { channel.take_message() });
-                            }
-                            // The sender was dropped while we were parked.
-                            DISCONNECTED => break Err(RecvTimeoutError::Disconnected),
-                            // State did not change, spurious wakeup, park again.
-                            RECEIVING => {
-                                if timed_out {
-                                    unsafe { channel.drop_waker() };
-                                    break Err(RecvTimeoutError::Timeout);
-                                }
-                            }
-                            _ => unreachable!(),
-                        }
-                    },
-                    // The sender sent the message while we prepared to park.
-                    Err(MESSAGE) => {
-                        channel.state.store(DISCONNECTED, SeqCst);
-                        unsafe { channel.drop_waker() };
-                        Ok(unsafe { channel.take_message() })
-                    }
-                    // The sender was dropped before sending anything while we prepared to park.
-                    Err(DISCONNECTED) => {
-                        unsafe { channel.drop_waker() };
-                        Err(RecvTimeoutError::Disconnected)
-                    }
-                    _ => unreachable!(),
-                }
-            }
-            // The sender sent the message.
-            MESSAGE => {
-                channel.state.store(DISCONNECTED, SeqCst);
-                Ok(unsafe { channel.take_message() })
-            }
-            // The sender was dropped before sending anything, or we already received the message.
-            DISCONNECTED => Err(RecvTimeoutError::Disconnected),
-            // The receiver must have been `Future::poll`ed prior to this call.
-            #[cfg(feature = "async")]
-            RECEIVING => panic!("{}", RECEIVER_USED_SYNC_AND_ASYNC_ERROR),
-            _ => unreachable!(),
+    /// Like [`Receiver::recv_deadline`], but blocks through `parker` instead of parking the
+    /// thread with the standard library, and measures the time until `deadline` with `clock`.
+    /// Available without the `std` feature, with the `park` feature. See the [`park`] module
+    /// for details.
+    ///
+    /// It is fine to call this method after this receiver has been polled asynchronously.
+    #[cfg(feature = "park")]
+    pub fn recv_deadline_with<P: Park, C: Clock>(
+        &self,
+        parker: &P,
+        clock: &C,
+        deadline: C::Instant,
+    ) -> Result<T, RecvTimeoutError> {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel.recv_ref(channel.wait_deadline_with(parker, clock, &deadline))
         }
     }
 }
 
-#[cfg(feature = "async")]
-impl<T> core::future::Future for Receiver<T> {
+#[cfg(all(feature = "alloc", feature = "async"))]
+impl<T, A: Allocator> core::future::Future for Receiver<T, A> {
     type Output = Result<T, RecvError>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
-        let channel = unsafe { self.channel_ptr.as_ref() };
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().poll_recv(cx) }
+    }
+}
 
-        match channel.state.load(SeqCst) {
-            // The sender is alive but has not sent anything yet.
-            EMPTY => unsafe { channel.write_async_waker(cx) },
-            // We were polled again while waiting for the sender. Replace the waker with the new one.
-            RECEIVING => {
-                match channel
-                    .state
-                    .compare_exchange(RECEIVING, EMPTY, SeqCst, SeqCst)
-                {
-                    // We successfully changed the state back to EMPTY. Replace the waker.
-                    Ok(RECEIVING) => {
-                        unsafe { channel.drop_waker() };
-                        unsafe { channel.write_async_waker(cx) }
-                    }
-                    // The sender sent the message while we prepared to replace the waker.
-                    // We take the message and mark the channel disconnected.
-                    // The sender has already taken the waker.
-                    Err(MESSAGE) => {
-                        channel.state.store(DISCONNECTED, SeqCst);
-                        Poll::Ready(Ok(unsafe { channel.take_message() }))
-                    }
-                    // The sender was dropped before sending anything while we prepared to park.
-                    // The sender has taken the waker already.
-                    Err(DISCONNECTED) => Poll::Ready(Err(RecvError)),
-                    _ => unreachable!(),
-                }
-            }
-            // The sender sent the message.
-            MESSAGE => {
-                channel.state.store(DISCONNECTED, SeqCst);
-                Poll::Ready(Ok(unsafe { channel.take_message() }))
-            }
-            // The sender was dropped before sending anything, or we already received the message.
-            DISCONNECTED => Poll::Ready(Err(RecvError)),
-            _ => unreachable!(),
-        }
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> fmt::Debug for Receiver<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("state", &self.state())
+            .finish_non_exhaustive()
     }
 }
 
-impl<T> Drop for Receiver<T> {
+#[cfg(feature = "alloc")]
+impl<T, A: Allocator> Drop for Receiver<T, A> {
     fn drop(&mut self) {
         // SAFETY: The reference won't be used after it is freed in this method
         let channel = unsafe { self.channel_ptr.as_ref() };
 
-        // Set the channel state to disconnected and read what state the receiver was in
-        match channel.state.swap(DISCONNECTED, SeqCst) {
-            // The sender has not sent anything, nor is it dropped.
-            EMPTY => (),
-            // The sender already sent something. We must drop it, and free the channel.
-            MESSAGE => {
-                unsafe { channel.drop_message() };
-                unsafe { dealloc(self.channel_ptr) };
-            }
-            // The receiver has been polled.
-            #[cfg(feature = "async")]
-            RECEIVING => {
-                unsafe { channel.drop_waker() };
-            }
-            // The sender was already dropped. We are responsible for freeing the channel.
-            DISCONNECTED => {
-                unsafe { dealloc(self.channel_ptr) };
-            }
-            _ => unreachable!(),
+        // If the sender already went away, we are responsible for freeing the channel.
+        unsafe { channel.drop_receiver(|| dealloc::<T, A>(self.channel_ptr)) };
+    }
+}
+
+/// A future that completes when the [`Receiver`] of a channel has been dropped. Created by
+/// [`Sender::closed`].
+#[cfg(all(feature = "alloc", feature = "async"))]
+#[derive(Debug)]
+pub struct Closed<'a, T, A: Allocator = Global> {
+    sender: &'a mut Sender<T, A>,
+}
+
+#[cfg(all(feature = "alloc", feature = "async"))]
+impl<'a, T, A: Allocator> core::future::Future for Closed<'a, T, A> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.sender.poll_closed(cx)
+    }
+}
+
+/// A future that completes when the channel of a [`Receiver`] is ready, without receiving
+/// anything. Created by [`Receiver::ready`].
+#[cfg(all(feature = "alloc", feature = "async"))]
+#[derive(Debug)]
+pub struct Ready<'a, T, A: Allocator = Global> {
+    receiver: &'a mut Receiver<T, A>,
+}
+
+#[cfg(all(feature = "alloc", feature = "async"))]
+impl<'a, T, A: Allocator> core::future::Future for Ready<'a, T, A> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.receiver.poll_ready(cx)
+    }
+}
+
+/// A handle to a message sent with [`Sender::send_acked`]. Tells when the [`Receiver`] has taken
+/// the message out of the channel, or gives the message back if the receiver was dropped without
+/// doing so.
+///
+/// The handle can be waited on with [`Delivery::wait`], or awaited as a `Future`. Dropping it
+/// does not affect the message, which is then handled as if it was sent with [`Sender::send`].
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+#[derive(Debug)]
+pub struct Delivery<T, A: Allocator = Global> {
+    channel_ptr: NonNull<Channel<T>>,
+    // The handle might drop a message that was handed back to it. See SendError for details
+    _dropck: PhantomData<T>,
+    // The allocator lives in the channel allocation, see `ChannelBlock`
+    _alloc: PhantomData<A>,
+}
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+unsafe impl<T: Send, A: Allocator + Send> Send for Delivery<T, A> {}
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+impl<T, A: Allocator> Unpin for Delivery<T, A> {}
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+impl<T, A: Allocator> Delivery<T, A> {
+    /// Blocks the current thread until the [`Receiver`] has taken the message out of the
+    /// channel, or has been dropped. Returns:
+    ///  * `Ok(())` if the receiver took the message.
+    ///  * `Err(Dropped(message))` if the receiver was dropped without taking the message.
+    ///
+    /// It is fine to call this method after this handle has been polled asynchronously.
+    #[cfg(feature = "std")]
+    pub fn wait(self) -> Result<(), Dropped<T>> {
+        // SAFETY: The channel will not be freed while this method is still running. Our Drop
+        // implementation takes care of releasing the channel afterwards.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel.take_ack(channel.wait_ack(None))
+        }
+    }
+
+    /// Like [`Delivery::wait`], but will not block longer than `timeout`. Returns true if the
+    /// [`Receiver`] took the message or was dropped before the timeout was reached. The result
+    /// can then be read with [`Delivery::wait`] without blocking.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking wait.
+    #[cfg(feature = "std")]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now().checked_add(timeout);
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe { self.channel_ptr.as_ref().wait_ack(deadline) != ACKING }
+    }
+}
+
+#[cfg(all(feature = "alloc", feature = "async"))]
+impl<T, A: Allocator> core::future::Future for Delivery<T, A> {
+    type Output = Result<(), Dropped<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        // SAFETY: The channel will not be freed while this method is still running.
+        unsafe {
+            let channel = self.channel_ptr.as_ref();
+            channel.poll_ack(cx).map(|state| channel.take_ack(state))
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+impl<T, A: Allocator> Drop for Delivery<T, A> {
+    fn drop(&mut self) {
+        // SAFETY: The reference won't be used after the channel is freed in this method
+        if unsafe { self.channel_ptr.as_ref().drop_ack() } {
+            // The receiver is gone. We are responsible for freeing the channel.
+            unsafe { dealloc::<T, A>(self.channel_ptr) };
         }
     }
 }
 
 /// All the values that the `Channel::state` field can have during the lifetime of a channel.
+///
+/// The values are chosen so that the sender can perform its transitions with a single wait-free
+/// `fetch_add` or `fetch_xor`, see `Sender::send` and `Sender::drop`.
 mod states {
-    /// The initial channel state. Active while both endpoints are still alive, no message has been
-    /// sent, and the receiver is not receiving.
-    pub const EMPTY: u8 = 0;
-    /// A message has been sent to the channel, but the receiver has not yet read it.
-    pub const MESSAGE: u8 = 1;
+    /// The type of the channel state. A 32 bit word where the receiver blocks on the state with
+    /// a futex, since futexes only operate on 32 bit words.
+    #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+    pub type State = u32;
+    #[cfg(not(all(target_os = "linux", feature = "std", not(loom))))]
+    pub type State = u8;
+
     /// No message has yet been sent on the channel, but the receiver is currently receiving.
-    pub const RECEIVING: u8 = 2;
+    #[cfg(any(feature = "park", feature = "async"))]
+    pub const RECEIVING: State = 0b000;
+    /// The sender has observed the RECEIVING state and is currently taking the waker out of the
+    /// channel in order to wake the receiver up. Only the sender can leave this state, and it
+    /// always does so by setting the MESSAGE, DISCONNECTED or PANICKED state. The receiver must
+    /// not touch the waker nor free the channel while in this state.
+    #[cfg(any(feature = "park", feature = "async"))]
+    pub const UNPARKING: State = 0b001;
     /// The channel has been closed. This means that either the sender or receiver has been dropped,
     /// or the message sent to the channel has already been received. Since this is a oneshot
     /// channel, it is disconnected after the one message it is supposed to hold has been
     /// transmitted.
-    pub const DISCONNECTED: u8 = 3;
+    pub const DISCONNECTED: State = 0b010;
+    /// The initial channel state. Active while both endpoints are still alive, no message has been
+    /// sent, and the receiver is not receiving.
+    pub const EMPTY: State = 0b011;
+    /// A message has been sent to the channel, but the receiver has not yet read it.
+    pub const MESSAGE: State = 0b100;
+    /// Like DISCONNECTED, but the sender was dropped without sending anything while its thread
+    /// was panicking. Only ever set with the `std` feature, since panics can't be detected
+    /// without it.
+    pub const PANICKED: State = 0b101;
 }
 use states::*;
 
+/// All the values that the `Channel::sender_state` field can have during the lifetime of a
+/// channel. This state is used by the sender to wait for the receiver to go away.
+#[cfg(any(feature = "park", feature = "async"))]
+mod sender_states {
+    /// The sender is not waiting for the receiver to go away. The sender waker is uninitialized.
+    pub const IDLE: u8 = 0;
+    /// The sender has written its waker to the channel and is waiting for the receiver to go away.
+    pub const WAITING: u8 = 1;
+    /// The receiver has gone away, or is in the process of doing so. If the sender had written a
+    /// waker to the channel, it has been taken by the receiver. Only an acked send can leave this
+    /// state, by moving it to ACKING or ACK_CLOSING.
+    pub const NOTIFIED: u8 = 2;
+
+    // The states below are only used after `Sender::send_acked`. The `Delivery` handle it returns
+    // then uses the sender waker to wait for the receiver to take the message. The handle counts
+    // as an endpoint, the channel is released by the last one of it and the receiver to go away.
+
+    /// The message was sent acknowledged and the receiver has not taken it yet. The sender
+    /// waker is uninitialized.
+    pub const ACKING: u8 = 3;
+    /// Like ACKING, but the `Delivery` handle has written its waker to the channel.
+    pub const ACK_WAITING: u8 = 4;
+    /// The receiver is taking the waker out of the channel in order to wake the `Delivery` handle
+    /// up. Only the receiver can leave this state, and it always does so by setting RECEIVED,
+    /// RECEIVED_CLOSED or RETURNED.
+    pub const ACK_UNPARKING: u8 = 5;
+    /// The receiver took the message out of the channel, and is still alive.
+    pub const RECEIVED: u8 = 6;
+    /// The receiver took the message out of the channel and has gone away. The `Delivery` handle
+    /// is responsible for releasing the channel.
+    pub const RECEIVED_CLOSED: u8 = 7;
+    /// The receiver went away without taking the message. The message is left in the channel,
+    /// and the `Delivery` handle is responsible for it and for releasing the channel.
+    pub const RETURNED: u8 = 8;
+    /// Like ACKING, but the message was sent while the receiver was going away. The receiver
+    /// might still be taking an earlier sender waker out of the channel, so the `Delivery`
+    /// handle must not write its waker until the receiver has reported RETURNED.
+    pub const ACK_CLOSING: u8 = 9;
+    /// The `Delivery` handle was dropped. The receiver behaves as if the message was sent with
+    /// a regular send.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    pub const ACK_DROPPED: u8 = 10;
+}
+#[cfg(any(feature = "park", feature = "async"))]
+use sender_states::*;
+
+/// All the values that the `Channel::origin` field can have. The origin decides what happens to
+/// the channel memory when the last endpoint goes away.
+mod origins {
+    /// The channel was allocated by `channel()`. The last endpoint to go away frees it.
+    #[cfg(feature = "alloc")]
+    pub const HEAP: u8 = 0;
+    /// The channel belongs to a `ReusableChannel` and is currently used by a sender and/or
+    /// receiver. The last endpoint to go away changes the origin to SLOT_FREE.
+    pub const SLOT_IN_USE: u8 = 1;
+    /// The channel belongs to a `ReusableChannel` and is not used by any endpoints. The
+    /// `ReusableChannel` can reset it and hand out new endpoints.
+    pub const SLOT_FREE: u8 = 2;
+    /// The channel was allocated by a `ChannelPool`. The last endpoint to go away returns it to
+    /// the pool.
+    #[cfg(feature = "alloc")]
+    pub const POOLED: u8 = 3;
+    /// The channel was allocated by `race_channel`. The last endpoint to go away drops the
+    /// channel's reference to the race block, which is freed once the race senders are gone too.
+    #[cfg(feature = "alloc")]
+    pub const RACED: u8 = 4;
+}
+#[cfg(feature = "alloc")]
+use origins::*;
+
 /// Internal channel data structure structure. the `channel` method allocates and puts one instance
 /// of this struct on the heap for each oneshot channel instance. The struct holds:
 /// * The current state of the channel.
 /// * The message in the channel. This memory is uninitialized until the message is sent.
 /// * The waker instance for the thread or task that is currently receiving on this channel.
 ///   This memory is uninitialized until the receiver starts receiving.
+/// * The state of, and the waker instance for, the sender waiting for the receiver to go away.
+///   The waker memory is uninitialized unless the sender is waiting.
+/// * The origin of the channel. Decides if the channel should be freed or recycled when both
+///   endpoints are gone.
 struct Channel<T> {
-    state: AtomicU8,
+    state: AtomicState,
+    origin: AtomicU8,
+    #[cfg(any(feature = "park", feature = "async"))]
+    sender_state: AtomicU8,
+    /// Set by the receiver before it enters the RECEIVING state. True if it waits in a task,
+    /// false if it blocks a thread.
+    #[cfg(any(feature = "park", feature = "async"))]
+    receiving_async: AtomicBool,
     message: UnsafeCell<MaybeUninit<T>>,
+    #[cfg(any(feature = "park", feature = "async"))]
     waker: UnsafeCell<MaybeUninit<ReceiverWaker>>,
+    #[cfg(any(feature = "park", feature = "async"))]
+    sender_waker: UnsafeCell<MaybeUninit<ReceiverWaker>>,
 }
 
 impl<T> Channel<T> {
-    pub fn new() -> Self {
+    // Loom's types can't be created in a const context. `StaticChannel::new` relies on this
+    // being const when not running under loom.
+    #[cfg(not(loom))]
+    pub const fn new(origin: u8) -> Self {
         Self {
-            state: AtomicU8::new(EMPTY),
+            state: AtomicState::new(EMPTY),
+            origin: AtomicU8::new(origin),
+            #[cfg(any(feature = "park", feature = "async"))]
+            sender_state: AtomicU8::new(IDLE),
+            #[cfg(any(feature = "park", feature = "async"))]
+            receiving_async: AtomicBool::new(false),
             message: UnsafeCell::new(MaybeUninit::uninit()),
+            #[cfg(any(feature = "park", feature = "async"))]
             waker: UnsafeCell::new(MaybeUninit::uninit()),
+            #[cfg(any(feature = "park", feature = "async"))]
+            sender_waker: UnsafeCell::new(MaybeUninit::uninit()),
         }
     }
 
+    #[cfg(loom)]
+    pub fn new(origin: u8) -> Self {
+        Self {
+            state: AtomicState::new(EMPTY),
+            origin: AtomicU8::new(origin),
+            #[cfg(any(feature = "park", feature = "async"))]
+            sender_state: AtomicU8::new(IDLE),
+            #[cfg(any(feature = "park", feature = "async"))]
+            receiving_async: AtomicBool::new(false),
+            message: UnsafeCell::new(MaybeUninit::uninit()),
+            #[cfg(any(feature = "park", feature = "async"))]
+            waker: UnsafeCell::new(MaybeUninit::uninit()),
+            #[cfg(any(feature = "park", feature = "async"))]
+            sender_waker: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Puts the channel back into its initial state, so a new pair of endpoints can use it.
+    /// Must only be called when no endpoints use the channel, and it holds no message or wakers.
+    ///
+    /// Relaxed ordering is enough, since handing the new endpoints to another thread
+    /// synchronizes with that thread anyway.
+    fn reset(&self) {
+        self.state.store(EMPTY, Relaxed);
+        #[cfg(any(feature = "park", feature = "async"))]
+        self.sender_state.store(IDLE, Relaxed);
+    }
+
+    /// Writes `message` to the channel and wakes the receiver up if it is waiting. Returns an
+    /// error if the receiver was already dropped. The message is then left in the channel, and
+    /// the caller is responsible for dropping it and releasing the channel.
+    unsafe fn send(&self, message: T) -> Result<(), ()> {
+        // If we have been waiting for the receiver to close, drop our waker. This must happen
+        // before we change the state, since the receiver might free the channel after that.
+        #[cfg(any(feature = "park", feature = "async"))]
+        self.unregister_sender_waker();
+
+        self.publish_message(message)
+    }
+
+    /// Like `send`, but tells the receiver to report back when it takes the message, or goes
+    /// away without doing so. On success the caller is responsible for calling `drop_ack` later.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    unsafe fn send_acked(&self, message: T) -> Result<(), ()> {
+        // Drop our waker if we were waiting for the receiver to go away. If the receiver has
+        // started going away instead, it owns the waker and might still be taking it out of the
+        // channel.
+        let acked_state = if self.unregister_sender_waker() {
+            ACKING
+        } else {
+            ACK_CLOSING
+        };
+
+        // Mark the send as acked, also if the receiver is going away. It only checks for an acked
+        // send after disconnecting the channel. If it finds our message then, it hands it back to
+        // the `Delivery` handle. Otherwise the send below fails. Either way we never wait for it.
+        //
+        // The acked state must be set before the message is visible to the receiver. Publishing
+        // the message below is Release, so this can be relaxed.
+        match self.sender_state.swap(acked_state, Relaxed) {
+            IDLE | NOTIFIED => (),
+            _ => unreachable!(),
+        }
+
+        self.publish_message(message)
+    }
+
+    /// Writes `message` to the channel and wakes the receiver up. The second half of `send`.
+    unsafe fn publish_message(&self, message: T) -> Result<(), ()> {
+        // Write the message into the channel.
+        self.write_message(message);
+
+        // Set the state to signal there is a message on the channel.
+        //
+        // EMPTY + 1 = MESSAGE
+        // RECEIVING + 1 = UNPARKING
+        // DISCONNECTED + 1 = EMPTY, which is never observed. The receiver is gone, and the
+        // caller only takes the message back out before freeing the channel or resetting it for
+        // reuse.
+        //
+        // Release publishes the message. Acquire is needed for reading the waker in the
+        // RECEIVING state and for taking back the message in the DISCONNECTED state.
+        match self.state.fetch_add(1, AcqRel) {
+            // The receiver is alive and has not started waiting. Send done.
+            EMPTY => Ok(()),
+            // The receiver is waiting. Wake it up so it can return the message.
+            #[cfg(any(feature = "park", feature = "async"))]
+            RECEIVING => {
+                self.unpark_receiver(MESSAGE);
+                Ok(())
+            }
+            // The receiver was already dropped.
+            DISCONNECTED => Err(()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Wakes up the receiver, after the sender moved the state from RECEIVING to UNPARKING.
+    /// Leaves the UNPARKING state by setting the final `state`, MESSAGE, DISCONNECTED or
+    /// PANICKED.
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn unpark_receiver(&self, state: State) {
+        // Take the waker, but critically do not unpark it yet. The receiver does not access the
+        // waker nor free the channel while we are in the UNPARKING state. But it might do so as
+        // soon as we set the final state.
+        let waker = self.take_waker();
+
+        // A receiver blocked in a futex wait is woken up through the state word, which is part
+        // of the channel. So it must be woken up before the final state is set. If it wakes up
+        // before that, it spins until the state leaves UNPARKING.
+        #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+        if let ReceiverWaker::Thread(ThreadWaker::Futex) = waker {
+            futex::wake(&self.state);
+            self.state.store(state, Release);
+            return;
+        }
+
+        // Same for an eventfd, which the receiver closes once it is dropped.
+        #[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+        if let ReceiverWaker::EventFd(fd) = waker {
+            pollable::signal(fd);
+            self.state.store(state, Release);
+            return;
+        }
+
+        self.state.store(state, Release);
+        waker.unpark();
+    }
+
+    /// Disconnects the channel from the sender side, waking the receiver up if it is waiting.
+    /// Returns true if the receiver was already dropped, in which case the caller is responsible
+    /// for releasing the channel.
+    unsafe fn drop_sender(&self) -> bool {
+        // If we have been waiting for the receiver to close, drop our waker. This must happen
+        // before we change the state, since the receiver might free the channel after that.
+        #[cfg(any(feature = "park", feature = "async"))]
+        self.unregister_sender_waker();
+
+        // Tell the receiver that the sender went away because of a panic.
+        #[cfg(feature = "std")]
+        if std::thread::panicking() {
+            return self.drop_sender_panicking();
+        }
+
+        // Set the channel state to disconnected and read what state the receiver was in
+        //
+        // EMPTY ^ 001 = DISCONNECTED
+        // RECEIVING ^ 001 = UNPARKING
+        // DISCONNECTED ^ 001 = invalid, however this state is never observed
+        match self.state.fetch_xor(0b001, AcqRel) {
+            // The receiver has not started waiting, nor is it dropped.
+            EMPTY => false,
+            // The receiver is waiting. Wake it up so it can detect that the channel disconnected.
+            #[cfg(any(feature = "park", feature = "async"))]
+            RECEIVING => {
+                self.unpark_receiver(DISCONNECTED);
+                false
+            }
+            // The receiver was already dropped.
+            DISCONNECTED => true,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The part of `drop_sender` that changes the state, when the sender is dropped during a
+    /// panic. The PANICKED state can't be reached with a single `fetch_xor` like DISCONNECTED,
+    /// so the transition is a compare-and-swap loop instead. The receiver can only move between
+    /// EMPTY and RECEIVING, or disconnect, while we try.
+    #[cfg(feature = "std")]
+    unsafe fn drop_sender_panicking(&self) -> bool {
+        let mut state = EMPTY;
+        loop {
+            let new_state = match state {
+                // The receiver has not started waiting, nor is it dropped.
+                EMPTY => PANICKED,
+                // The receiver is waiting. We wake it up below.
+                RECEIVING => UNPARKING,
+                // The receiver was already dropped. Observed with Acquire by the failed
+                // compare-and-swap, so we are allowed to release the channel.
+                DISCONNECTED => return true,
+                _ => unreachable!(),
+            };
+            match self
+                .state
+                .compare_exchange(state, new_state, AcqRel, Acquire)
+            {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
+        }
+
+        if state == RECEIVING {
+            self.unpark_receiver(PANICKED);
+        }
+        false
+    }
+
+    /// Returns a snapshot of the channel state. Can be called by either endpoint.
+    fn channel_state(&self) -> ChannelState {
+        // Nothing is handed over by only looking at the state. The flag telling how the receiver
+        // waits is written before the RECEIVING state is entered, with Release, so it is visible
+        // once we observe that state.
+        match self.state.load(Acquire) {
+            EMPTY => ChannelState::Empty,
+            MESSAGE => ChannelState::Message,
+            #[cfg(any(feature = "park", feature = "async"))]
+            RECEIVING | UNPARKING => ChannelState::Receiving {
+                sync: !self.receiving_async.load(Relaxed),
+            },
+            DISCONNECTED | PANICKED => ChannelState::Disconnected,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns true if the receiver has been dropped. Must only be called by the sender.
+    fn is_closed(&self) -> bool {
+        // The receiver is the only one able to set the disconnected state while the sender is
+        // alive. Nothing is handed over to the sender by that, so the load can be relaxed.
+        self.state.load(Relaxed) == DISCONNECTED
+    }
+
+    /// Waits for the receiver to be dropped, or for `deadline` to pass if it is not `None`.
+    /// Returns true if the receiver was dropped. Must only be called by the sender.
+    #[cfg(feature = "std")]
+    unsafe fn wait_closed(&self, deadline: Option<Instant>) -> bool {
+        if self.is_closed() {
+            return true;
+        }
+
+        if self
+            .register_sender_waker(ReceiverWaker::current_thread())
+            .is_err()
+        {
+            // The receiver is being dropped, it will disconnect the channel momentarily.
+            while !self.is_closed() {
+                thread::yield_now();
+            }
+            return true;
+        }
+
+        // Our waker is registered. Park until the receiver has disconnected the channel.
+        loop {
+            match deadline.map(|deadline| deadline.checked_duration_since(Instant::now())) {
+                None => thread::park(),
+                Some(Some(timeout)) => thread::park_timeout(timeout),
+                // We reached the deadline. Try to remove our waker again.
+                Some(None) => {
+                    if self.unregister_sender_waker() {
+                        return false;
+                    }
+                    // The receiver took our waker. It will unpark us once it has disconnected
+                    // the channel. Keep parking until that happens.
+                    while !self.is_closed() {
+                        thread::park();
+                    }
+                    return true;
+                }
+            }
+
+            // The receiver only unparks us after it has disconnected the channel, so anything
+            // else is a spurious wakeup.
+            if self.is_closed() {
+                return true;
+            }
+        }
+    }
+
+    /// Polls whether the receiver has been dropped. Must only be called by the sender.
+    #[cfg(feature = "async")]
+    unsafe fn poll_closed(&self, cx: &mut task::Context<'_>) -> Poll<()> {
+        if self.is_closed() {
+            return Poll::Ready(());
+        }
+
+        match self.register_sender_waker(ReceiverWaker::task_waker(cx)) {
+            // Our waker is registered. The receiver will wake us up when it is dropped.
+            Ok(()) => Poll::Pending,
+            // The receiver is currently being dropped, but has not yet disconnected the channel.
+            // It is not going to wake anyone up, so we wake ourselves up to get polled again.
+            Err(()) if !self.is_closed() => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Err(()) => Poll::Ready(()),
+        }
+    }
+
+    /// Takes the message out of the channel if there is one, without blocking.
+    unsafe fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.state.load(Acquire) {
+            // The sender is alive but has not sent anything yet.
+            EMPTY => Err(TryRecvError::Empty),
+            // The sender sent the message. We take the message and mark the channel disconnected.
+            MESSAGE => Ok(self.receive_message()),
+            // The sender was dropped before sending anything, or we already received the message.
+            DISCONNECTED => Err(TryRecvError::SenderDropped),
+            // The sender was dropped during a panic before sending anything.
+            PANICKED => Err(TryRecvError::SenderPanicked),
+            // The receiver must have already been `Future::poll`ed, or waits through an eventfd.
+            // No message available.
+            #[cfg(any(feature = "park", feature = "async"))]
+            RECEIVING => Err(TryRecvError::Empty),
+            // The sender is in the middle of waking up the receiver. The message is not yet
+            // available.
+            #[cfg(any(feature = "park", feature = "async"))]
+            UNPARKING => Err(TryRecvError::Empty),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Receives the result of waiting without a deadline, which returned `state`. Then goes away
+    /// from the receiver side. Calls `release` if the receiver was the last endpoint. The channel
+    /// must not be touched after `release` has been called.
+    #[cfg(feature = "park")]
+    unsafe fn recv(&self, state: State, release: impl FnOnce()) -> Result<T, RecvError> {
+        let result = match state {
+            // The sender sent the message.
+            MESSAGE => Ok(self.take_message()),
+            // The sender was dropped before sending anything, or we already received the message.
+            DISCONNECTED => Err(RecvError::SenderDropped),
+            // The sender was dropped during a panic before sending anything.
+            PANICKED => Err(RecvError::SenderPanicked),
+            _ => unreachable!(),
+        };
+
+        // The sender is gone by now, unless it sent the message acked and still holds on to
+        // the `Delivery` handle.
+        if self.finish_ack(RECEIVED_CLOSED) {
+            release();
+        }
+        result
+    }
+
+    /// Receives the result of waiting, which returned `state`, without going away from the
+    /// receiver side.
+    #[cfg(feature = "park")]
+    unsafe fn recv_ref(&self, state: State) -> Result<T, RecvTimeoutError> {
+        match state {
+            // The sender sent the message. We take the message and mark the channel disconnected.
+            MESSAGE => Ok(self.receive_message()),
+            // The sender was dropped before sending anything, or we already received the message.
+            DISCONNECTED => Err(RecvTimeoutError::SenderDropped),
+            // The sender was dropped during a panic before sending anything.
+            PANICKED => Err(RecvTimeoutError::SenderPanicked),
+            // The deadline was reached before anything happened.
+            EMPTY => Err(RecvTimeoutError::Timeout),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The `Future::poll` implementation for the receiver.
+    #[cfg(feature = "async")]
+    unsafe fn poll_recv(&self, cx: &mut task::Context<'_>) -> Poll<Result<T, RecvError>> {
+        self.poll_ready(cx).map(|state| match state {
+            // The sender sent the message. We take the message and mark the channel disconnected.
+            MESSAGE => Ok(self.receive_message()),
+            // The sender was dropped before sending anything, or we already received the message.
+            DISCONNECTED => Err(RecvError::SenderDropped),
+            // The sender was dropped during a panic before sending anything.
+            PANICKED => Err(RecvError::SenderPanicked),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Polls until the sender has sent the message or been dropped, registering the waker of
+    /// `cx` until then. Returns the final state, MESSAGE, DISCONNECTED or PANICKED, without
+    /// receiving anything. The waker is never left in the channel when this returns `Ready`.
+    #[cfg(feature = "async")]
+    unsafe fn poll_ready(&self, cx: &mut task::Context<'_>) -> Poll<State> {
+        match self.state.load(Acquire) {
+            // The sender is alive but has not sent anything yet.
+            EMPTY => self.write_async_waker(cx),
+            // We were polled again while waiting for the sender. Replace the waker with the new one.
+            RECEIVING => {
+                match self
+                    .state
+                    .compare_exchange(RECEIVING, EMPTY, Acquire, Acquire)
+                {
+                    // We successfully changed the state back to EMPTY. Replace the waker.
+                    Ok(RECEIVING) => {
+                        self.drop_waker();
+                        self.write_async_waker(cx)
+                    }
+                    // The sender is waking up the old waker while we prepared to replace it.
+                    // We can't trust that the runtime honors wakeups of old wakers. So we wake
+                    // ourselves up to get polled again once the sender is done.
+                    Err(UNPARKING) => {
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                    // The sender sent the message or was dropped while we prepared to replace
+                    // the waker. The sender has taken the waker already.
+                    Err(state) => Poll::Ready(state),
+                    _ => unreachable!(),
+                }
+            }
+            // The sender is currently waking us up. Same as above, wake ourselves up to get
+            // polled again once the sender is done.
+            UNPARKING => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            // The sender sent the message or was dropped, or we already received the message.
+            state => Poll::Ready(state),
+        }
+    }
+
+    /// Disconnects the channel from the receiver side, dropping any message in it and waking
+    /// the sender up if it waits for this. Calls `release` if the sender was already gone. The
+    /// channel must not be touched after `release` has been called.
+    unsafe fn drop_receiver(&self, release: impl FnOnce()) {
+        self.disconnect_receiver(false, release);
+    }
+
+    /// Like `drop_receiver`, but returns any message in the channel instead of dropping it.
+    unsafe fn close(&self, release: impl FnOnce()) -> Option<T> {
+        self.disconnect_receiver(true, release)
+    }
+
+    unsafe fn disconnect_receiver(&self, take_message: bool, release: impl FnOnce()) -> Option<T> {
+        // Tell the sender we are going away, and take its waker if it is waiting for that.
+        // This must happen before we change the state, since the sender might free the channel
+        // after that. A message sent acked, also one sent while we do this, is handled by
+        // `finish_ack` below instead.
+        #[cfg(any(feature = "park", feature = "async"))]
+        let sender_waker = self.notify_sender();
+
+        // If the receiver has been polled, or waits through an eventfd, get rid of the waker
+        // before disconnecting.
+        #[cfg(any(feature = "park", feature = "async"))]
+        self.stop_receiving(self.state.load(Acquire));
+
+        // Set the channel state to disconnected and read what state the receiver was in
+        let message = match self.state.swap(DISCONNECTED, AcqRel) {
+            // The sender has not sent anything, nor is it dropped. If it is in the middle of an
+            // acked send, that send fails now.
+            EMPTY => None,
+            // The sender already sent something. We take it out, it counts as received.
+            MESSAGE if take_message => {
+                let message = self.take_message();
+                #[cfg(any(feature = "park", feature = "async"))]
+                if !self.finish_ack(RECEIVED_CLOSED) {
+                    return Some(message);
+                }
+                release();
+                Some(message)
+            }
+            // The sender already sent something. We must drop it, and free the channel. Unless
+            // it was sent acked, then the message is handed back to the `Delivery` handle.
+            MESSAGE => {
+                #[cfg(any(feature = "park", feature = "async"))]
+                if !self.finish_ack(RETURNED) {
+                    return None;
+                }
+                self.drop_message();
+                release();
+                None
+            }
+            // The sender was already dropped, or a `Delivery` handle might still be alive. We
+            // are responsible for freeing the channel if it is not.
+            DISCONNECTED | PANICKED => {
+                #[cfg(any(feature = "park", feature = "async"))]
+                if !self.finish_ack(RECEIVED_CLOSED) {
+                    return None;
+                }
+                release();
+                None
+            }
+            _ => unreachable!(),
+        };
+
+        // The sender waker is owned by us at this point, not by the channel. So it is fine to
+        // use it even if the sender freed the channel in the meantime.
+        #[cfg(any(feature = "park", feature = "async"))]
+        if let Ok(Some(sender_waker)) = sender_waker {
+            sender_waker.unpark();
+        }
+        message
+    }
+
+    /// Takes the message out of the channel and marks the channel disconnected. Must only be
+    /// called by the receiver, after observing the MESSAGE state.
+    unsafe fn receive_message(&self) -> T {
+        // The sender is gone, or only its `Delivery` handle is left which does not look at the
+        // channel state. Only we will look at this state again.
+        self.state.store(DISCONNECTED, Relaxed);
+        let message = self.take_message();
+        #[cfg(any(feature = "park", feature = "async"))]
+        self.finish_ack(RECEIVED);
+        message
+    }
+
     #[inline(always)]
     unsafe fn message(&self) -> &MaybeUninit<T> {
         #[cfg(loom)]
@@ -775,7 +1736,7 @@ impl<T> Channel<T> {
     }
 
     #[inline(always)]
-    #[cfg(any(feature = "std", feature = "async"))]
+    #[cfg(any(feature = "park", feature = "async"))]
     unsafe fn with_waker_mut<F>(&self, op: F)
     where
         F: FnOnce(&mut MaybeUninit<ReceiverWaker>),
@@ -791,6 +1752,23 @@ impl<T> Channel<T> {
         }
     }
 
+    #[inline(always)]
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn with_sender_waker_mut<F>(&self, op: F)
+    where
+        F: FnOnce(&mut MaybeUninit<ReceiverWaker>),
+    {
+        #[cfg(loom)]
+        {
+            self.sender_waker.with_mut(|ptr| op(&mut *ptr))
+        }
+
+        #[cfg(not(loom))]
+        {
+            op(&mut *self.sender_waker.get())
+        }
+    }
+
     #[inline(always)]
     unsafe fn write_message(&self, message: T) {
         self.with_message_mut(|slot| slot.as_mut_ptr().write(message));
@@ -814,12 +1792,13 @@ impl<T> Channel<T> {
         self.with_message_mut(|slot| slot.assume_init_drop());
     }
 
-    #[cfg(any(feature = "std", feature = "async"))]
+    #[cfg(any(feature = "park", feature = "async"))]
     #[inline(always)]
     unsafe fn write_waker(&self, waker: ReceiverWaker) {
         self.with_waker_mut(|slot| slot.as_mut_ptr().write(waker));
     }
 
+    #[cfg(any(feature = "park", feature = "async"))]
     #[inline(always)]
     unsafe fn take_waker(&self) -> ReceiverWaker {
         #[cfg(loom)]
@@ -833,53 +1812,565 @@ impl<T> Channel<T> {
         }
     }
 
-    #[cfg(any(feature = "std", feature = "async"))]
+    #[cfg(any(feature = "park", feature = "async"))]
     #[inline(always)]
     unsafe fn drop_waker(&self) {
         self.with_waker_mut(|slot| slot.assume_init_drop());
     }
 
+    /// Writes `waker` to the channel so the receiver can wake the sender up when it goes away.
+    /// Any previously registered sender waker is dropped first. Returns an error, and drops
+    /// `waker`, if the receiver has already started going away.
+    #[cfg(any(feature = "std", feature = "async"))]
+    unsafe fn register_sender_waker(&self, waker: ReceiverWaker) -> Result<(), ()> {
+        // Take back any previously registered waker, so we have exclusive access to the slot.
+        if !self.unregister_sender_waker() {
+            return Err(());
+        }
+
+        self.with_sender_waker_mut(|slot| slot.as_mut_ptr().write(waker));
+
+        match self
+            .sender_state
+            .compare_exchange(IDLE, WAITING, Release, Relaxed)
+        {
+            // We stored our waker, the receiver will take it and wake us up when it goes away.
+            Ok(IDLE) => Ok(()),
+            // The receiver started going away while we prepared our waker.
+            Err(NOTIFIED) => {
+                self.with_sender_waker_mut(|slot| slot.assume_init_drop());
+                Err(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Makes sure the `Delivery` handle is no longer in the ACK_WAITING state, given that the
+    /// sender state was just observed to be `state`. Works like `stop_receiving`, but for the
+    /// sender waker.
+    ///
+    /// Returns the new state, which is one of ACKING, ACK_CLOSING, RECEIVED, RECEIVED_CLOSED
+    /// or RETURNED.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    unsafe fn stop_ack_waiting(&self, mut state: u8) -> u8 {
+        loop {
+            match state {
+                ACK_WAITING => {
+                    match self
+                        .sender_state
+                        .compare_exchange(ACK_WAITING, ACKING, Acquire, Acquire)
+                    {
+                        Ok(ACK_WAITING) => {
+                            self.with_sender_waker_mut(|slot| slot.assume_init_drop());
+                            return ACKING;
+                        }
+                        Err(new_state) => state = new_state,
+                        _ => unreachable!(),
+                    }
+                }
+                // The receiver is taking the waker. It will report the outcome momentarily,
+                // with no blocking operations in between.
+                ACK_UNPARKING => {
+                    hint::spin_loop();
+                    state = self.sender_state.load(Acquire);
+                }
+                state => return state,
+            }
+        }
+    }
+
+    /// Registers the waker returned by `waker` for the `Delivery` handle. Returns ACK_WAITING if
+    /// it was stored. Otherwise returns ACK_CLOSING if the receiver is about to report RETURNED,
+    /// or the outcome the receiver has already reported.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    unsafe fn start_ack_waiting(&self, waker: impl FnOnce() -> ReceiverWaker) -> u8 {
+        match self.stop_ack_waiting(self.sender_state.load(Acquire)) {
+            ACKING => (),
+            // Includes ACK_CLOSING. The waker slot might still be in use by the receiver.
+            state => return state,
+        }
+
+        self.with_sender_waker_mut(|slot| slot.as_mut_ptr().write(waker()));
+
+        match self
+            .sender_state
+            .compare_exchange(ACKING, ACK_WAITING, AcqRel, Acquire)
+        {
+            Ok(ACKING) => ACK_WAITING,
+            // The receiver took the message or went away while we prepared our waker.
+            Err(state) => {
+                self.with_sender_waker_mut(|slot| slot.assume_init_drop());
+                state
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Blocks the `Delivery` handle until the receiver has taken the message or gone away, or
+    /// until `deadline` has passed if it is not `None`. Returns the new sender state, ACKING if
+    /// the deadline passed first.
+    #[cfg(feature = "std")]
+    unsafe fn wait_ack(&self, deadline: Option<Instant>) -> u8 {
+        loop {
+            match self.start_ack_waiting(ReceiverWaker::current_thread) {
+                ACK_WAITING => break,
+                // The receiver is going away, and reports RETURNED momentarily.
+                ACK_CLOSING => thread::yield_now(),
+                state => return state,
+            }
+        }
+
+        loop {
+            match deadline.map(|deadline| deadline.checked_duration_since(Instant::now())) {
+                None => thread::park(),
+                Some(Some(timeout)) => thread::park_timeout(timeout),
+                // We reached the deadline. Take back our waker.
+                Some(None) => return self.stop_ack_waiting(ACK_WAITING),
+            }
+            match self.sender_state.load(Acquire) {
+                // Spurious wakeup, or the receiver is about to wake us up. Park again.
+                ACK_WAITING | ACK_UNPARKING => (),
+                state => return state,
+            }
+        }
+    }
+
+    /// The `Future::poll` implementation for the `Delivery` handle.
+    #[cfg(all(feature = "alloc", feature = "async"))]
+    unsafe fn poll_ack(&self, cx: &mut task::Context<'_>) -> Poll<u8> {
+        match self.start_ack_waiting(|| ReceiverWaker::task_waker(cx)) {
+            ACK_WAITING => Poll::Pending,
+            // The receiver is going away, and reports RETURNED momentarily. We can't register
+            // our waker yet, so have the task polled again instead.
+            ACK_CLOSING => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            state => Poll::Ready(state),
+        }
+    }
+
+    /// Turns an outcome reported by the receiver into the result of the `Delivery` handle.
+    /// A returned message is taken out of the channel.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    unsafe fn take_ack(&self, state: u8) -> Result<(), Dropped<T>> {
+        match state {
+            RECEIVED | RECEIVED_CLOSED => Ok(()),
+            // We own the channel and the message. With the message gone, the channel is in the
+            // same situation as if the receiver had taken it.
+            RETURNED => {
+                let message = self.take_message();
+                self.sender_state.store(RECEIVED_CLOSED, Relaxed);
+                Err(Dropped(message))
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Drops the `Delivery` handle. Returns true if the receiver is already gone, in which case
+    /// the caller is responsible for releasing the channel.
+    #[cfg(all(feature = "alloc", any(feature = "std", feature = "async")))]
+    unsafe fn drop_ack(&self) -> bool {
+        let mut state = self.sender_state.load(Acquire);
+        loop {
+            match self.stop_ack_waiting(state) {
+                // The receiver is alive. Leave the channel to it.
+                current @ (ACKING | ACK_CLOSING | RECEIVED) => {
+                    match self
+                        .sender_state
+                        .compare_exchange(current, ACK_DROPPED, AcqRel, Acquire)
+                    {
+                        Ok(_) => return false,
+                        Err(new_state) => state = new_state,
+                    }
+                }
+                RECEIVED_CLOSED => return true,
+                RETURNED => {
+                    self.drop_message();
+                    return true;
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    #[cfg(any(feature = "park", feature = "async"))]
+    #[inline(always)]
+    unsafe fn take_sender_waker(&self) -> ReceiverWaker {
+        #[cfg(loom)]
+        {
+            self.sender_waker.with(|ptr| ptr::read(ptr)).assume_init()
+        }
+
+        #[cfg(not(loom))]
+        {
+            ptr::read(self.sender_waker.get()).assume_init()
+        }
+    }
+
+    /// Drops the sender waker if one is registered. Returns false if the receiver has started
+    /// going away, in which case it has taken any registered waker.
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn unregister_sender_waker(&self) -> bool {
+        match self
+            .sender_state
+            .compare_exchange(WAITING, IDLE, Relaxed, Relaxed)
+        {
+            Ok(WAITING) => {
+                self.with_sender_waker_mut(|slot| slot.assume_init_drop());
+                true
+            }
+            Err(IDLE) => true,
+            Err(NOTIFIED) => false,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Marks the receiver as going away. Returns the sender waker if the sender is waiting for
+    /// this to happen, so it can be woken up. Returns an error, and leaves the sender state
+    /// untouched, if the message has been sent acked.
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn notify_sender(&self) -> Result<Option<ReceiverWaker>, ()> {
+        let mut state = IDLE;
+        loop {
+            match state {
+                IDLE | WAITING => {
+                    match self
+                        .sender_state
+                        .compare_exchange(state, NOTIFIED, Acquire, Relaxed)
+                    {
+                        Ok(IDLE) => return Ok(None),
+                        Ok(WAITING) => return Ok(Some(self.take_sender_waker())),
+                        // The sender registered or unregistered its waker, or started an acked
+                        // send, in the meantime.
+                        Err(new_state) => state = new_state,
+                        _ => unreachable!(),
+                    }
+                }
+                _ => return Err(()),
+            }
+        }
+    }
+
+    /// Reports to the `Delivery` handle of an acked send that the receiver took the message or
+    /// went away, by moving the sender state to `outcome`. Returns true if there is no
+    /// `Delivery` handle to report to. Then the receiver behaves as for a regular send.
+    ///
+    /// After reporting RECEIVED_CLOSED or RETURNED the channel must not be touched anymore.
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn finish_ack(&self, outcome: u8) -> bool {
+        let mut state = self.sender_state.load(Acquire);
+        loop {
+            match state {
+                // The handle is not waiting. Just report the outcome.
+                ACKING | ACK_CLOSING | RECEIVED => match self
+                    .sender_state
+                    .compare_exchange(state, outcome, AcqRel, Acquire)
+                {
+                    Ok(_) => return false,
+                    Err(new_state) => state = new_state,
+                },
+                // The handle is waiting. Take its waker before reporting the outcome, since the
+                // handle might free the channel as soon as it sees RECEIVED_CLOSED or RETURNED.
+                ACK_WAITING => match self.sender_state.compare_exchange(
+                    ACK_WAITING,
+                    ACK_UNPARKING,
+                    Acquire,
+                    Acquire,
+                ) {
+                    Ok(ACK_WAITING) => {
+                        let waker = self.take_sender_waker();
+                        self.sender_state.store(outcome, Release);
+                        waker.unpark();
+                        return false;
+                    }
+                    Err(new_state) => state = new_state,
+                    _ => unreachable!(),
+                },
+                // Either a regular send, or the handle was dropped.
+                _ => return true,
+            }
+        }
+    }
+
+    /// Makes sure the receiver is no longer in the RECEIVING state, given that the channel was
+    /// just observed to be in `state`. If the sender has not started waking the receiver up, the
+    /// state is reset to EMPTY and the waker is dropped. If the sender is currently waking the
+    /// receiver up, this spins until it is done.
+    ///
+    /// Returns the new state, which is one of EMPTY, MESSAGE, DISCONNECTED or PANICKED.
+    #[cfg(any(feature = "park", feature = "async"))]
+    unsafe fn stop_receiving(&self, mut state: State) -> State {
+        loop {
+            match state {
+                RECEIVING => {
+                    match self
+                        .state
+                        .compare_exchange(RECEIVING, EMPTY, Acquire, Acquire)
+                    {
+                        Ok(RECEIVING) => {
+                            self.drop_waker();
+                            return EMPTY;
+                        }
+                        Err(new_state) => state = new_state,
+                        _ => unreachable!(),
+                    }
+                }
+                // The sender is taking the waker. It will set MESSAGE, DISCONNECTED or PANICKED
+                // momentarily, with no blocking operations in between.
+                UNPARKING => {
+                    hint::spin_loop();
+                    state = self.state.load(Acquire);
+                }
+                state => return state,
+            }
+        }
+    }
+
+    /// Busy-waits for the sender as described by `strategy`, then blocks like `wait` without a
+    /// deadline if it has not acted yet. The busy-waiting only loads the state, so if the sender
+    /// acts during it no waker is written to the channel.
+    #[cfg(feature = "std")]
+    unsafe fn busy_wait(&self, strategy: RecvStrategy) -> State {
+        for i in 0..strategy.spins.saturating_add(strategy.yields) {
+            if let state @ (MESSAGE | DISCONNECTED | PANICKED) = self.state.load(Acquire) {
+                return state;
+            }
+            if i < strategy.spins {
+                hint::spin_loop();
+            } else {
+                thread::yield_now();
+            }
+        }
+        self.wait(None)
+    }
+
+    /// Registers the waker returned by `waker` in the channel, so the sender wakes the receiver
+    /// up when it sends the message or is dropped. Any waker written to the channel earlier is
+    /// dropped first.
+    ///
+    /// Returns RECEIVING if the waker was stored. Otherwise the sender has already acted, the
+    /// waker is not left in the channel and MESSAGE, DISCONNECTED or PANICKED is returned.
+    #[cfg(feature = "park")]
+    unsafe fn start_waiting(&self, waker: impl FnOnce() -> ReceiverWaker) -> State {
+        // Drop any task waker left behind by an earlier `Future::poll` call.
+        match self.stop_receiving(self.state.load(Acquire)) {
+            // The sender is alive but has not sent anything yet. We prepare to wait.
+            EMPTY => (),
+            // The sender sent the message or was dropped. No need to wait.
+            state => return state,
+        }
+
+        // Conditionally add a delay here to help the tests trigger the edge cases where
+        // the sender manages to be dropped or send something before we are able to store
+        // our waker object in the channel.
+        #[cfg(oneshot_test_delay)]
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        // Write our waker instance to the channel. Everything waiting through here blocks a
+        // thread, except for an eventfd.
+        let waker = waker();
+        #[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+        let asynchronous = matches!(waker, ReceiverWaker::EventFd(_));
+        #[cfg(not(all(target_os = "linux", feature = "eventfd", not(loom))))]
+        let asynchronous = false;
+        self.write_waker(waker);
+        self.receiving_async.store(asynchronous, Relaxed);
+
+        match self
+            .state
+            .compare_exchange(EMPTY, RECEIVING, AcqRel, Acquire)
+        {
+            // We stored our waker, the sender will wake us up.
+            Ok(EMPTY) => RECEIVING,
+            // The sender sent the message or was dropped while we prepared to wait.
+            Err(state) => {
+                self.drop_waker();
+                state
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// Registers the waker returned by `waker` in the channel, replacing any waker written by an
+    /// earlier poll of the receiver. Then calls `park` until the sender has sent the message or
+    /// been dropped. `park` must block until the waker is woken up, or return false if the
+    /// deadline has passed. It is allowed to return spuriously.
+    ///
+    /// Returns the new state. MESSAGE, DISCONNECTED or PANICKED if the sender acted, or EMPTY if
+    /// the deadline passed first. The waker is never left in the channel when this returns.
+    #[cfg(feature = "park")]
+    unsafe fn block(
+        &self,
+        waker: impl FnOnce() -> ReceiverWaker,
+        mut park: impl FnMut() -> bool,
+    ) -> State {
+        match self.start_waiting(waker) {
+            // We stored our waker, now we park until the sender has changed the state.
+            RECEIVING => (),
+            // The sender sent the message or was dropped. No need to wait.
+            state => return state,
+        }
+
+        loop {
+            if !park() {
+                // We reached the deadline. Stop being in the receiving state.
+                return self.stop_receiving(RECEIVING);
+            }
+            match self.state.load(Acquire) {
+                // State did not change, spurious wakeup, park again. If the sender is currently
+                // unparking us, it will do so after setting the final state. So we park again
+                // in that case too.
+                RECEIVING | UNPARKING => (),
+                // The sender sent the message or was dropped while we were parked.
+                state => return state,
+            }
+        }
+    }
+
+    /// Blocks with `parker` until the sender has sent the message or been dropped. See `block`.
+    #[cfg(feature = "park")]
+    unsafe fn wait_with<P: Park>(&self, parker: &P) -> State {
+        let waker = || ReceiverWaker::Task(parker.unparker().into_waker());
+        self.block(waker, || {
+            parker.park();
+            true
+        })
+    }
+
+    /// Blocks with `parker` until the sender has sent the message or been dropped, or until
+    /// `deadline` on `clock` has passed. See `block`.
+    #[cfg(feature = "park")]
+    unsafe fn wait_deadline_with<P: Park, C: Clock>(
+        &self,
+        parker: &P,
+        clock: &C,
+        deadline: &C::Instant,
+    ) -> State {
+        let waker = || ReceiverWaker::Task(parker.unparker().into_waker());
+        self.block(waker, || match clock.time_until(deadline) {
+            Some(timeout) => {
+                parker.park_timeout(timeout);
+                true
+            }
+            // We reached the deadline.
+            None => false,
+        })
+    }
+
+    /// Blocks the current thread until the sender has sent the message or been dropped, or until
+    /// `deadline` has passed if it is not `None`. See `block`.
+    #[cfg(all(
+        feature = "std",
+        not(all(target_os = "linux", feature = "std", not(loom)))
+    ))]
+    unsafe fn wait(&self, deadline: Option<Instant>) -> State {
+        self.block(ReceiverWaker::current_thread, || {
+            match deadline.map(|deadline| deadline.checked_duration_since(Instant::now())) {
+                None => thread::park(),
+                Some(Some(timeout)) => thread::park_timeout(timeout),
+                // We reached the deadline.
+                Some(None) => return false,
+            }
+            true
+        })
+    }
+
+    /// Like the other `wait`, but blocks in a futex wait directly on the state word, instead of
+    /// parking the thread. The deadline is converted into an absolute timeout once, so spurious
+    /// wakeups don't need a new timeout computed.
+    #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+    unsafe fn wait(&self, deadline: Option<Instant>) -> State {
+        match self.start_waiting(ReceiverWaker::futex) {
+            // We are in the receiving state, now we block until the sender has changed it.
+            RECEIVING => (),
+            // The sender sent the message or was dropped. No need to wait.
+            state => return state,
+        }
+
+        // A deadline too far in the future to represent is the same as no deadline.
+        let deadline = deadline.and_then(futex::Deadline::new);
+        loop {
+            match self.state.load(Acquire) {
+                // Returns immediately if the state changed after we loaded it. Otherwise blocks
+                // until the sender wakes us up, which it does before leaving UNPARKING.
+                RECEIVING => {
+                    if !futex::wait(&self.state, RECEIVING, deadline.as_ref()) {
+                        // We reached the deadline. Stop being in the receiving state.
+                        return self.stop_receiving(RECEIVING);
+                    }
+                }
+                // The sender has woken us up, or is about to. It sets the final state
+                // momentarily, with no blocking operations in between.
+                UNPARKING => hint::spin_loop(),
+                // The sender sent the message or was dropped while we were blocked.
+                state => return state,
+            }
+        }
+    }
+
     #[cfg(feature = "async")]
-    unsafe fn write_async_waker(&self, cx: &mut task::Context<'_>) -> Poll<Result<T, RecvError>> {
+    unsafe fn write_async_waker(&self, cx: &mut task::Context<'_>) -> Poll<State> {
         // Write our thread instance to the channel.
         self.write_waker(ReceiverWaker::task_waker(cx));
+        self.receiving_async.store(true, Relaxed);
 
         match self
             .state
-            .compare_exchange(EMPTY, RECEIVING, SeqCst, SeqCst)
+            .compare_exchange(EMPTY, RECEIVING, AcqRel, Acquire)
         {
             // We stored our waker, now we return and let the sender wake us up
             Ok(EMPTY) => Poll::Pending,
-            // The sender was dropped before sending anything while we prepared to park.
-            Err(DISCONNECTED) => {
-                self.drop_waker();
-                Poll::Ready(Err(RecvError))
-            }
-            // The sender sent the message while we prepared to park.
-            // We take the message and mark the channel disconnected.
-            Err(MESSAGE) => {
+            // The sender sent the message or was dropped while we prepared to park.
+            Err(state) => {
                 self.drop_waker();
-                self.state.store(DISCONNECTED, SeqCst);
-                Poll::Ready(Ok(self.take_message()))
+                Poll::Ready(state)
             }
             _ => unreachable!(),
         }
     }
 }
 
+#[cfg_attr(not(any(feature = "park", feature = "async")), allow(dead_code))]
 enum ReceiverWaker {
-    /// The receiver is waiting synchronously. Its thread is parked.
+    /// The receiver is waiting synchronously. Its thread is blocked.
     #[cfg(feature = "std")]
-    Thread(thread::Thread),
-    /// The receiver is waiting asynchronously. Its task can be woken up with this `Waker`.
-    #[cfg(feature = "async")]
+    Thread(ThreadWaker),
+    /// The receiver is waiting asynchronously, or blocked in a custom `Park` implementation.
+    /// It can be woken up with this `Waker`.
+    #[cfg(any(feature = "park", feature = "async"))]
     Task(task::Waker),
+    /// The receiver waits through an eventfd, in an event loop. See `PollableReceiver`.
+    #[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+    EventFd(std::os::unix::io::RawFd),
+}
+
+/// How a thread waiting synchronously is woken up. Not part of `ReceiverWaker` itself, since
+/// `ReceiverWaker` then could not fit the futex variant into the niche of `Waker`, and would grow.
+#[cfg(feature = "std")]
+enum ThreadWaker {
+    /// The thread is parked.
+    Park(thread::Thread),
+    /// The thread is blocked in a futex wait on the channel state. Woken up by
+    /// `Channel::unpark_receiver`, since that needs the address of the state.
+    #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+    Futex,
 }
 
 impl ReceiverWaker {
     #[cfg(feature = "std")]
     pub fn current_thread() -> Self {
-        Self::Thread(thread::current())
+        Self::thread(thread::current())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn thread(thread: thread::Thread) -> Self {
+        Self::Thread(ThreadWaker::Park(thread))
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+    pub fn futex() -> Self {
+        Self::Thread(ThreadWaker::Futex)
     }
 
     #[cfg(feature = "async")]
@@ -887,12 +2378,21 @@ impl ReceiverWaker {
         Self::Task(cx.waker().clone())
     }
 
+    #[cfg(any(feature = "park", feature = "async"))]
     pub fn unpark(self) {
         match self {
             #[cfg(feature = "std")]
-            ReceiverWaker::Thread(thread) => thread.unpark(),
-            #[cfg(feature = "async")]
+            ReceiverWaker::Thread(ThreadWaker::Park(thread)) => thread.unpark(),
+            #[cfg(all(target_os = "linux", feature = "std", not(loom)))]
+            ReceiverWaker::Thread(ThreadWaker::Futex) => {
+                unreachable!("futex waiters are woken up through the channel")
+            }
+            #[cfg(any(feature = "park", feature = "async"))]
             ReceiverWaker::Task(waker) => waker.wake(),
+            #[cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+            ReceiverWaker::EventFd(_) => {
+                unreachable!("eventfd waiters are woken up through the channel")
+            }
         }
     }
 }
@@ -900,20 +2400,84 @@ impl ReceiverWaker {
 #[cfg(not(loom))]
 #[test]
 fn receiver_waker_size() {
-    let expected: usize = match (cfg!(feature = "std"), cfg!(feature = "async")) {
+    let expected: usize = match (cfg!(feature = "park"), cfg!(feature = "async")) {
         (false, false) => 0,
-        (false, true) => 16,
-        (true, false) => 8,
-        (true, true) => 24,
+        // The eventfd does not fit into a niche of the other variants.
+        _ if cfg!(all(target_os = "linux", feature = "eventfd")) => 24,
+        _ => 16,
     };
-    assert_eq!(mem::size_of::<ReceiverWaker>(), expected);
+    assert_eq!(core::mem::size_of::<ReceiverWaker>(), expected);
 }
 
-#[cfg(all(feature = "std", feature = "async"))]
-const RECEIVER_USED_SYNC_AND_ASYNC_ERROR: &str =
-    "Invalid to call a blocking receive method on oneshot::Receiver after it has been polled";
+/// The heap allocation backing a channel created by `channel_in`. Holds the allocator the
+/// channel was allocated in, so the last endpoint can free the channel through it.
+#[cfg(feature = "alloc")]
+#[repr(C)]
+struct ChannelBlock<T, A> {
+    /// Must be the first field, so a pointer to the channel is also a pointer to the block.
+    channel: Channel<T>,
+    alloc: A,
+    /// Lets loom detect leaked channels, since allocations through `A` are not tracked by it.
+    #[cfg(loom)]
+    _track: loom::alloc::Track<()>,
+}
+
+/// Moves `channel` into a new allocation in `alloc` and returns a pointer to it.
+#[cfg(feature = "alloc")]
+fn allocate<T, A: Allocator>(channel: Channel<T>, alloc: A) -> NonNull<Channel<T>> {
+    let layout = Layout::new::<ChannelBlock<T, A>>();
+    let block = match alloc.allocate(layout) {
+        Ok(block) => block.cast::<ChannelBlock<T, A>>(),
+        Err(_) => handle_alloc_error(layout),
+    };
+
+    // SAFETY: The allocator returned memory fitting `layout`.
+    unsafe {
+        block.as_ptr().write(ChannelBlock {
+            channel,
+            alloc,
+            #[cfg(loom)]
+            _track: loom::alloc::Track::new(()),
+        })
+    };
+    block.cast()
+}
 
+/// Called by the last endpoint of a channel to go away. Frees the channel, hands it back to the
+/// `ReusableChannel` or `ChannelPool` it belongs to, or releases it to its race senders.
+///
+/// # Safety
+///
+/// No endpoint may use the channel anymore, and any message must already have been dropped or
+/// taken out. `A` must be the allocator the channel was allocated in.
+#[cfg(feature = "alloc")]
 #[inline]
-pub(crate) unsafe fn dealloc<T>(channel: NonNull<Channel<T>>) {
-    drop(Box::from_raw(channel.as_ptr()))
+pub(crate) unsafe fn dealloc<T, A: Allocator>(channel: NonNull<Channel<T>>) {
+    let origin = &channel.as_ref().origin;
+    match origin.load(SeqCst) {
+        HEAP => (),
+        POOLED => return pool::recycle(channel),
+        RACED => return race::release(channel),
+        _ => {
+            if origin
+                .compare_exchange(SLOT_IN_USE, SLOT_FREE, SeqCst, SeqCst)
+                .is_ok()
+            {
+                // The `ReusableChannel` is still alive and will reuse the channel.
+                return;
+            }
+        }
+    }
+
+    let block = channel.cast::<ChannelBlock<T, A>>().as_ptr();
+    // Move the allocator out of the block before the memory goes away. It is dropped when it
+    // goes out of scope at the end of this function.
+    let alloc = ptr::read(&(*block).alloc);
+    ptr::drop_in_place(&mut (*block).channel);
+    #[cfg(loom)]
+    ptr::drop_in_place(&mut (*block)._track);
+    alloc.deallocate(
+        NonNull::new_unchecked(block).cast(),
+        Layout::new::<ChannelBlock<T, A>>(),
+    );
 }
@@ -0,0 +1,170 @@
+//! Traits for plugging a custom way of blocking into the blocking receive methods.
+//!
+//! The blocking receive methods such as [`Receiver::recv`](crate::Receiver::recv) block the
+//! thread with the standard library, and only exist with the `std` feature. The `park` feature
+//! adds receive methods that instead block through a [`Park`] implementation, and measure time
+//! with a [`Clock`] implementation, supplied by the caller. This makes blocking receive possible
+//! without the standard library, for example on an RTOS. Or it can block a green thread or
+//! stackful coroutine instead of the OS thread it runs on.
+//!
+//! With the `std` feature, [`ThreadParker`] and [`StdClock`] implement the traits with the
+//! standard library.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "std")] {
+//! use oneshot::park::{StdClock, ThreadParker};
+//! use std::time::{Duration, Instant};
+//!
+//! let (sender, receiver) = oneshot::channel();
+//! std::thread::spawn(move || sender.send(5u32));
+//!
+//! let deadline = Instant::now() + Duration::from_secs(10);
+//! let parker = ThreadParker::new();
+//! assert_eq!(receiver.recv_deadline_with(&parker, &StdClock, deadline), Ok(5));
+//! # }
+//! ```
+
+use core::task::Waker;
+use core::time::Duration;
+
+/// A way to block the current thread of execution until it is woken up again.
+///
+/// A receiver blocking through a parker first writes the parker's [`unparker`](Park::unparker)
+/// into the channel. It then calls [`park`](Park::park) or [`park_timeout`](Park::park_timeout)
+/// in a loop, until the sender has woken it up by sending the message or being dropped.
+pub trait Park {
+    /// The handle unparking this parker.
+    type Unparker: Unpark;
+
+    /// Returns the handle that unparks this parker. Called every time a receiver blocks, so it
+    /// should be cheap.
+    fn unparker(&self) -> Self::Unparker;
+
+    /// Blocks the current thread of execution until it is unparked. Can return spuriously.
+    fn park(&self);
+
+    /// Blocks the current thread of execution until it is unparked, or until `timeout` has
+    /// elapsed. Can return spuriously.
+    fn park_timeout(&self, timeout: Duration);
+}
+
+/// The handle waking up a [`Park`] implementation, returned by [`Park::unparker`].
+///
+/// It is implemented for [`Waker`], which can be created without allocating from any handle
+/// fitting in a pointer with a [`RawWaker`](core::task::RawWaker). With the `alloc` feature it
+/// is also implemented for `Arc<W>` of any [`Wake`](alloc::task::Wake) implementation.
+pub trait Unpark {
+    /// Makes a concurrent call to `park` or `park_timeout` return. If it is called before the
+    /// parker parks, the next call to park must return immediately.
+    fn unpark(&self);
+
+    /// Converts the handle into a [`Waker`] calling [`unpark`](Unpark::unpark) when woken. The
+    /// channel stores the handle in this form, so it does not need to know its type.
+    fn into_waker(self) -> Waker;
+}
+
+impl Unpark for Waker {
+    fn unpark(&self) {
+        self.wake_by_ref()
+    }
+
+    fn into_waker(self) -> Waker {
+        self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<W: alloc::task::Wake + Send + Sync + 'static> Unpark for alloc::sync::Arc<W> {
+    fn unpark(&self) {
+        W::wake_by_ref(self)
+    }
+
+    fn into_waker(self) -> Waker {
+        Waker::from(self)
+    }
+}
+
+/// A clock measuring the time until the deadline of a blocking receive operation.
+pub trait Clock {
+    /// A point in time on this clock.
+    type Instant;
+
+    /// Returns how long it is until `deadline`, or `None` if the deadline has been reached.
+    fn time_until(&self, deadline: &Self::Instant) -> Option<Duration>;
+}
+
+/// A [`Park`] implementation parking the OS thread that created it, with the standard library.
+///
+/// Its [`unparker`](Park::unparker) is allocated once, when the parker is created. Keep the
+/// parker around to block the same thread many times without allocating. The receive methods not
+/// taking a parker never allocate, and are preferable when no custom parker is needed.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct ThreadParker {
+    unparker: Waker,
+    // The parker parks the current thread, so it must stay on the thread it unparks.
+    _not_send: core::marker::PhantomData<*const ()>,
+}
+
+#[cfg(feature = "std")]
+impl ThreadParker {
+    /// Creates a parker for the current thread.
+    pub fn new() -> Self {
+        Self {
+            unparker: Waker::from(std::sync::Arc::new(
+                ThreadUnparker(crate::thread::current()),
+            )),
+            _not_send: core::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ThreadParker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Park for ThreadParker {
+    type Unparker = Waker;
+
+    fn unparker(&self) -> Waker {
+        self.unparker.clone()
+    }
+
+    fn park(&self) {
+        crate::thread::park()
+    }
+
+    fn park_timeout(&self, timeout: Duration) {
+        crate::thread::park_timeout(timeout)
+    }
+}
+
+#[cfg(feature = "std")]
+struct ThreadUnparker(crate::thread::Thread);
+
+#[cfg(feature = "std")]
+impl std::task::Wake for ThreadUnparker {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+/// A [`Clock`] measuring time with [`std::time::Instant`].
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    type Instant = std::time::Instant;
+
+    fn time_until(&self, deadline: &Self::Instant) -> Option<Duration> {
+        deadline.checked_duration_since(std::time::Instant::now())
+    }
+}
@@ -0,0 +1,142 @@
+//! Receiving through a file descriptor that becomes readable once the channel is ready, for
+//! event loops built on `epoll` or `mio` instead of an async runtime.
+//!
+//! [`Receiver::into_pollable`] turns a receiver into a [`PollableReceiver`]. It holds a Linux
+//! eventfd, registered in the channel in place of the waker of a waiting receiver. The
+//! [`Sender`](crate::Sender) signals the eventfd when it sends the message or is dropped, which
+//! makes it readable. The event loop then calls [`PollableReceiver::try_recv`] to get the message.
+//!
+//! ```rust
+//! use std::os::unix::io::AsRawFd;
+//!
+//! let (sender, receiver) = oneshot::channel();
+//! let receiver = receiver.into_pollable().unwrap();
+//!
+//! // Register `receiver.as_raw_fd()` for readability in an epoll instance or similar.
+//! # let _ = receiver.as_raw_fd();
+//!
+//! sender.send("done").unwrap();
+//! // Once the event loop reports the file descriptor as readable:
+//! assert_eq!(receiver.try_recv(), Ok("done"));
+//! ```
+//!
+//! With the `mio` feature [`PollableReceiver`] implements `mio::event::Source`, and can be
+//! registered with a `mio::Poll` directly.
+
+use super::states::*;
+use super::{Allocator, ChannelState, Global, Receiver, ReceiverWaker, TryRecvError};
+use core::fmt;
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+
+impl<T, A: Allocator> Receiver<T, A> {
+    /// Turns the receiver into a [`PollableReceiver`], with a file descriptor that becomes
+    /// readable once the channel is ready. That is once the [`Sender`](crate::Sender) has sent
+    /// the message or been dropped. See the [`pollable`](crate::pollable) module.
+    ///
+    /// Returns an error if the eventfd could not be created.
+    pub fn into_pollable(self) -> io::Result<PollableReceiver<T, A>> {
+        // SAFETY: eventfd takes no pointers. The flags are valid.
+        let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: The eventfd was just created, and is owned by nothing else.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: The channel will not be freed while this method is still running. The waker
+        // stays in the channel until the sender takes it or the receiver is dropped, and the
+        // eventfd is closed only after the receiver is dropped.
+        let channel = unsafe { self.channel_ptr.as_ref() };
+        match unsafe { channel.start_waiting(|| ReceiverWaker::EventFd(fd.as_raw_fd())) } {
+            // Our eventfd is registered. The sender signals it when it acts.
+            RECEIVING => (),
+            // The sender sent the message or was dropped already. The channel is ready now.
+            _ => signal(fd.as_raw_fd()),
+        }
+
+        Ok(PollableReceiver { receiver: self, fd })
+    }
+}
+
+/// A [`Receiver`] that can be waited on through a file descriptor. Created by
+/// [`Receiver::into_pollable`].
+///
+/// The file descriptor becomes readable once the channel is ready, and stays readable from then
+/// on. Even after the message has been received, since the channel is then disconnected, which
+/// also counts as ready.
+pub struct PollableReceiver<T, A: Allocator = Global> {
+    // Must be dropped before the eventfd is closed, since the sender might signal the eventfd
+    // until the receiver has been dropped.
+    receiver: Receiver<T, A>,
+    fd: OwnedFd,
+}
+
+impl<T, A: Allocator> PollableReceiver<T, A> {
+    /// Checks if there is a message in the channel without blocking. Works like
+    /// [`Receiver::try_recv`]. Meant to be called once the file descriptor is readable, it then
+    /// never returns `Err(Empty)`.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Returns a snapshot of the channel state. See [`Receiver::state`].
+    pub fn state(&self) -> ChannelState {
+        self.receiver.state()
+    }
+}
+
+impl<T, A: Allocator> AsRawFd for PollableReceiver<T, A> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl<T, A: Allocator> AsFd for PollableReceiver<T, A> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(feature = "mio")]
+impl<T, A: Allocator> mio::event::Source for PollableReceiver<T, A> {
+    fn register(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.fd.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &mio::Registry,
+        token: mio::Token,
+        interests: mio::Interest,
+    ) -> io::Result<()> {
+        mio::unix::SourceFd(&self.fd.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &mio::Registry) -> io::Result<()> {
+        mio::unix::SourceFd(&self.fd.as_raw_fd()).deregister(registry)
+    }
+}
+
+impl<T, A: Allocator> fmt::Debug for PollableReceiver<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollableReceiver")
+            .field("state", &self.state())
+            .field("fd", &self.fd.as_raw_fd())
+            .finish()
+    }
+}
+
+/// Makes the eventfd `fd` readable. Called by the sender before it sets the final state, since
+/// the receiver might close the eventfd right after that.
+pub(crate) fn signal(fd: RawFd) {
+    let value: u64 = 1;
+    // SAFETY: `value` is valid for reading 8 bytes, the size eventfd expects. The write can
+    // only fail if the counter would overflow, in which case the eventfd is readable anyway.
+    unsafe { libc::write(fd, &value as *const u64 as *const libc::c_void, 8) };
+}
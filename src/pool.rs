@@ -0,0 +1,230 @@
+use super::{endpoints, origins::POOLED, Channel, Receiver, Sender};
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+
+#[cfg(not(loom))]
+use alloc::{boxed::Box, sync::Arc};
+#[cfg(not(loom))]
+use core::sync::atomic::{AtomicPtr, Ordering::SeqCst};
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{AtomicPtr, Ordering::SeqCst},
+    Arc,
+};
+
+#[cfg(loom)]
+use super::loombox::Box;
+
+/// A pool of channel allocations. Creating channels from a pool, with [`ChannelPool::channel`],
+/// reuses the memory of earlier channels from the same pool instead of allocating.
+///
+/// When both endpoints of a pooled channel are gone, the last one of them puts the channel
+/// allocation back into the pool instead of freeing it. This can happen on any thread. The
+/// endpoints are the same [`Sender`] and [`Receiver`] types as the ones returned from
+/// [`channel`](crate::channel) and behave identically.
+///
+/// The pool never shrinks. It keeps as many allocations as the highest number of its channels
+/// that were alive at the same time. All of them are freed when the pool is dropped. Channels
+/// still alive at that point are freed by their endpoints as usual.
+///
+/// The pool itself can be moved between threads but not shared between them. Give each thread
+/// that creates channels its own pool.
+///
+/// ```rust
+/// let pool = oneshot::ChannelPool::new();
+/// for i in 0..3 {
+///     // Only the first iteration allocates.
+///     let (sender, receiver) = pool.channel();
+///     sender.send(i).unwrap();
+///     assert_eq!(receiver.try_recv(), Ok(i));
+/// }
+/// assert_eq!(pool.idle(), 1);
+/// ```
+pub struct ChannelPool<T> {
+    /// Unused blocks only the pool itself can reach, linked through `PoolBlock::next`. Taking
+    /// blocks from here does not need any synchronization.
+    local: Cell<*mut PoolBlock<T>>,
+    shared: Arc<Shared<T>>,
+    _dropck: PhantomData<T>,
+}
+
+/// The part of the pool that pooled channels need to reach when they are done.
+struct Shared<T> {
+    /// The top of a stack of unused blocks returned by endpoints, linked through
+    /// `PoolBlock::next`. Null if the stack is empty. Set to `closed()` when the pool is dropped.
+    ///
+    /// Endpoints push single blocks, on any thread. The pool only ever takes the entire stack at
+    /// once. That keeps the stack free from the ABA problem.
+    head: AtomicPtr<PoolBlock<T>>,
+}
+
+/// The allocation backing a pooled channel.
+#[repr(C)]
+struct PoolBlock<T> {
+    /// Must be the first field, so a pointer to the channel is also a pointer to the block.
+    channel: Channel<T>,
+    pool: Arc<Shared<T>>,
+    /// The block below this one in the free list. Only valid while the block is in the list.
+    next: *mut PoolBlock<T>,
+}
+
+/// Marker value for `Shared::head` telling endpoints that the pool is gone and that they
+/// should free their block instead of returning it. Never the address of a real block.
+#[inline(always)]
+fn closed<T>() -> *mut PoolBlock<T> {
+    NonNull::dangling().as_ptr()
+}
+
+unsafe impl<T: Send> Send for ChannelPool<T> {}
+
+impl<T> ChannelPool<T> {
+    /// Creates a new, empty pool. Does not allocate any channels.
+    pub fn new() -> Self {
+        Self {
+            local: Cell::new(ptr::null_mut()),
+            shared: Arc::new(Shared {
+                head: AtomicPtr::new(ptr::null_mut()),
+            }),
+            _dropck: PhantomData,
+        }
+    }
+
+    /// Creates a new pool with `capacity` channel allocations ready for use.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let pool = Self::new();
+        for _ in 0..capacity {
+            let block = pool.allocate();
+            // SAFETY: The block is newly allocated and only reachable by us.
+            unsafe { (*block).next = pool.local.get() };
+            pool.local.set(block);
+        }
+        pool
+    }
+
+    /// Creates a new oneshot channel and returns the two endpoints, [`Sender`] and [`Receiver`].
+    /// Reuses an allocation from the pool if there is one, otherwise allocates a new one.
+    pub fn channel(&self) -> (Sender<T>, Receiver<T>) {
+        let block = match self.pop() {
+            Some(block) => {
+                // SAFETY: Blocks in the free lists are not used by any endpoints. They left the
+                // channel without a message or waker in it.
+                unsafe { (*block).channel.reset() };
+                block
+            }
+            None => self.allocate(),
+        };
+
+        // SAFETY: `block` is not null. The channel is in its initial state, and no endpoints
+        // are using it.
+        unsafe { endpoints(NonNull::new_unchecked(block).cast()) }
+    }
+
+    /// Returns the number of channel allocations currently in the pool, ready to be reused.
+    pub fn idle(&self) -> usize {
+        // Move all blocks to the local list, so we can walk them without racing with endpoints.
+        self.take_shared();
+        let mut count = 0;
+        let mut block = self.local.get();
+        while !block.is_null() {
+            count += 1;
+            // SAFETY: Blocks in the local list are only reachable by us.
+            block = unsafe { (*block).next };
+        }
+        count
+    }
+
+    fn allocate(&self) -> *mut PoolBlock<T> {
+        Box::into_raw(Box::new(PoolBlock {
+            channel: Channel::new(POOLED),
+            pool: self.shared.clone(),
+            next: ptr::null_mut(),
+        }))
+    }
+
+    /// Takes a block out of the local list. Refills the local list with the blocks endpoints
+    /// returned if it is empty.
+    fn pop(&self) -> Option<*mut PoolBlock<T>> {
+        if self.local.get().is_null() {
+            self.take_shared();
+        }
+        let block = self.local.get();
+        if block.is_null() {
+            return None;
+        }
+        // SAFETY: Blocks in the local list are only reachable by us.
+        self.local.set(unsafe { (*block).next });
+        Some(block)
+    }
+
+    /// Moves all blocks returned by endpoints over to the local list.
+    fn take_shared(&self) {
+        let mut block = self.shared.head.swap(ptr::null_mut(), SeqCst);
+        while !block.is_null() {
+            // SAFETY: We took the whole stack, so the blocks are only reachable by us.
+            let next = unsafe { (*block).next };
+            unsafe { (*block).next = self.local.get() };
+            self.local.set(block);
+            block = next;
+        }
+    }
+}
+
+impl<T> Default for ChannelPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ChannelPool<T> {
+    fn drop(&mut self) {
+        // Close the pool, so endpoints still alive free their channel instead of returning it.
+        let shared = self.shared.head.swap(closed(), SeqCst);
+        for mut block in [shared, self.local.get()] {
+            while !block.is_null() {
+                // SAFETY: The block was in one of the free lists, which we now own. Nothing else
+                // uses it.
+                let next = unsafe { (*block).next };
+                drop(unsafe { Box::from_raw(block) });
+                block = next;
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for ChannelPool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelPool")
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+/// Puts the channel back into the pool it came from, or frees it if the pool is gone.
+///
+/// # Safety
+///
+/// The channel must have been allocated by a `ChannelPool`, and no endpoints may use it anymore.
+pub(crate) unsafe fn recycle<T>(channel: NonNull<Channel<T>>) {
+    let block = channel.cast::<PoolBlock<T>>().as_ptr();
+
+    // The block stops being ours once it is pushed. Another thread might then drop the pool and
+    // free the block, and the shared pool state with it. So we must not touch any of them after
+    // a successful push.
+    let shared: &Shared<T> = &(*block).pool;
+
+    let mut head = shared.head.load(SeqCst);
+    loop {
+        if head == closed() {
+            drop(Box::from_raw(block));
+            return;
+        }
+        (*block).next = head;
+        match shared.head.compare_exchange(head, block, SeqCst, SeqCst) {
+            Ok(_) => return,
+            // Another endpoint pushed, or the pool took all blocks or was dropped. Try again.
+            Err(new_head) => head = new_head,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use super::{origins::RACED, Channel, Receiver};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+#[cfg(not(loom))]
+use alloc::boxed::Box;
+#[cfg(not(loom))]
+use core::sync::atomic::{
+    AtomicBool, AtomicUsize,
+    Ordering::{AcqRel, Relaxed},
+};
+#[cfg(loom)]
+use loom::sync::atomic::{
+    AtomicBool, AtomicUsize,
+    Ordering::{AcqRel, Relaxed},
+};
+
+#[cfg(loom)]
+use super::loombox::Box;
+
+/// Creates a new oneshot channel with a sender that can be cloned, and returns the two
+/// endpoints, [`RaceSender`] and [`Receiver`].
+///
+/// All clones of the sender race to send the message. The first successful send wins, and
+/// later ones get their message back in a [`RaceSendError::Fulfilled`] error. The receiver is
+/// the same [`Receiver`] type as the one returned from [`channel`](crate::channel). It only
+/// sees a [`RecvError`](crate::RecvError) once all senders were dropped without sending.
+///
+/// ```rust
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use std::thread;
+///
+/// let (sender, receiver) = oneshot::race_channel();
+/// for replica in ["replica-a", "replica-b", "replica-c"] {
+///     let sender = sender.clone();
+///     thread::spawn(move || {
+///         // Only the replica answering first gets through.
+///         let _ = sender.send(replica);
+///     });
+/// }
+/// drop(sender);
+///
+/// let winner = receiver.recv().unwrap();
+/// assert!(winner.starts_with("replica-"));
+/// # }
+/// ```
+pub fn race_channel<T>() -> (RaceSender<T>, Receiver<T>) {
+    let block = Box::into_raw(Box::new(RaceBlock {
+        channel: Channel::new(RACED),
+        senders: AtomicUsize::new(1),
+        fulfilled: AtomicBool::new(false),
+        references: AtomicUsize::new(2),
+    }));
+    // SAFETY: `Box::into_raw` never returns null.
+    let block = unsafe { NonNull::new_unchecked(block) };
+    (
+        RaceSender {
+            block,
+            _dropck: PhantomData,
+        },
+        Receiver {
+            channel_ptr: block.cast(),
+            _dropck: PhantomData,
+            _alloc: PhantomData,
+        },
+    )
+}
+
+/// The allocation backing a race channel.
+///
+/// The channel inside is used as a regular oneshot channel, with a single sender. That sender
+/// is the first clone of the [`RaceSender`] to claim the block through `fulfilled`, either to
+/// send the message or, if none was sent, as the last one to be dropped. The block is freed once
+/// both the channel has been released by its last endpoint and all race senders are gone.
+#[repr(C)]
+struct RaceBlock<T> {
+    /// Must be the first field, so a pointer to the channel is also a pointer to the block.
+    channel: Channel<T>,
+    /// The number of `RaceSender` clones alive.
+    senders: AtomicUsize,
+    /// Set by the sender that acts as the channel's sender.
+    fulfilled: AtomicBool,
+    /// One reference held by the channel until it is released, one by the race senders
+    /// together.
+    references: AtomicUsize,
+}
+
+/// The sending half of a race channel, created by [`race_channel`]. Can be cloned. The first
+/// clone to successfully send wins.
+pub struct RaceSender<T> {
+    block: NonNull<RaceBlock<T>>,
+    // See SendError for details
+    _dropck: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for RaceSender<T> {}
+unsafe impl<T: Send> Sync for RaceSender<T> {}
+
+impl<T> RaceSender<T> {
+    /// Sends `message` to the [`Receiver`], unless another clone of this sender already did.
+    /// Returns:
+    ///  * `Ok(())` if this sender won the race and the message is in the channel.
+    ///  * `Err(Fulfilled(message))` if another sender has already sent its message.
+    ///  * `Err(Disconnected(message))` if the receiver was dropped before any message was sent.
+    ///
+    /// Apart from deciding the race with a single atomic swap, this works like
+    /// [`Sender::send`](crate::Sender::send).
+    pub fn send(self, message: T) -> Result<(), RaceSendError<T>> {
+        // SAFETY: The block is alive as long as any race sender is.
+        let block = unsafe { self.block.as_ref() };
+        // The swap only decides which sender acts as the channel's sender. Nothing is handed
+        // over through it, so it can be relaxed.
+        if block.fulfilled.swap(true, Relaxed) {
+            return Err(RaceSendError::Fulfilled(message));
+        }
+
+        // We are now the channel's only sender.
+        match unsafe { block.channel.send(message) } {
+            Ok(()) => Ok(()),
+            Err(()) => {
+                // The receiver is gone. We own the message left in the channel, and are the
+                // last endpoint of it.
+                let message = unsafe { block.channel.take_message() };
+                unsafe { release::<T>(self.block.cast()) };
+                Err(RaceSendError::Disconnected(message))
+            }
+        }
+    }
+
+    /// Returns true if a clone of this sender has already sent its message, or the last
+    /// clone is being dropped. Any future call to [`RaceSender::send`] then returns an error.
+    pub fn is_fulfilled(&self) -> bool {
+        // SAFETY: The block is alive as long as any race sender is.
+        unsafe { self.block.as_ref() }.fulfilled.load(Relaxed)
+    }
+}
+
+impl<T> Clone for RaceSender<T> {
+    fn clone(&self) -> Self {
+        // SAFETY: The block is alive as long as any race sender is.
+        unsafe { self.block.as_ref() }.senders.fetch_add(1, Relaxed);
+        Self {
+            block: self.block,
+            _dropck: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for RaceSender<T> {
+    fn drop(&mut self) {
+        // SAFETY: The block is alive as long as any race sender is. We don't touch it after
+        // releasing our reference below.
+        let block = unsafe { self.block.as_ref() };
+        if block.senders.fetch_sub(1, AcqRel) != 1 {
+            return;
+        }
+
+        // We are the last sender. If nobody sent anything, we disconnect the channel as its
+        // sender.
+        if !block.fulfilled.swap(true, Relaxed) && unsafe { block.channel.drop_sender() } {
+            // The receiver was already dropped. We are responsible for releasing the channel.
+            unsafe { release::<T>(self.block.cast()) };
+        }
+        unsafe { release::<T>(self.block.cast()) };
+    }
+}
+
+impl<T> fmt::Debug for RaceSender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RaceSender")
+            .field("fulfilled", &self.is_fulfilled())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Drops one reference to the race block of `channel`, and frees the block if it was the last
+/// one. Called by the last endpoint of the channel, and by the last race sender.
+///
+/// # Safety
+///
+/// The channel must have been allocated by `race_channel`, and the caller must hold one of the
+/// references. It must not touch the block afterwards.
+pub(crate) unsafe fn release<T>(channel: NonNull<Channel<T>>) {
+    let block = channel.cast::<RaceBlock<T>>().as_ptr();
+    if (*block).references.fetch_sub(1, AcqRel) == 1 {
+        drop(Box::from_raw(block));
+    }
+}
+
+/// An error returned from [`RaceSender::send`]. Contains the message that could not be sent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RaceSendError<T> {
+    /// Another clone of the sender already sent its message.
+    Fulfilled(T),
+
+    /// The [`Receiver`] was dropped before any message was sent.
+    Disconnected(T),
+}
+
+impl<T> RaceSendError<T> {
+    /// Consumes the error and returns the message that failed to be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            RaceSendError::Fulfilled(message) | RaceSendError::Disconnected(message) => message,
+        }
+    }
+}
+
+impl<T> fmt::Display for RaceSendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            RaceSendError::Fulfilled(_) => "channel already fulfilled",
+            RaceSendError::Disconnected(_) => "sending on a closed channel",
+        };
+        msg.fmt(f)
+    }
+}
+
+impl<T> fmt::Debug for RaceSendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaceSendError::Fulfilled(_) => write!(f, "Fulfilled<{}>(_)", stringify!(T)),
+            RaceSendError::Disconnected(_) => write!(f, "Disconnected<{}>(_)", stringify!(T)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for RaceSendError<T> {}
@@ -0,0 +1,63 @@
+//! Receive errors of the channels carrying more than one message. Unlike the oneshot
+//! [`RecvError`](crate::RecvError) they can't tell a panicked sender apart from a dropped one,
+//! since the channel only disconnects once all of its senders are gone.
+
+use core::fmt;
+
+/// An error returned from the indefinitely blocking receive methods and the receive future.
+///
+/// Receiving can only fail if the channel is empty and all senders have been dropped.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "receiving on a closed channel".fmt(f)
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+/// An error returned when trying a non blocking receive.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TryRecvError {
+    /// The channel is empty, but senders are still alive.
+    Empty,
+
+    /// The channel is empty, and all senders have been dropped.
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            TryRecvError::Empty => "receiving on an empty channel",
+            TryRecvError::Disconnected => "receiving on a closed channel",
+        };
+        msg.fmt(f)
+    }
+}
+
+impl std::error::Error for TryRecvError {}
+
+/// An error returned from a time limited blocking receive.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum RecvTimeoutError {
+    /// No message arrived before the timeout was reached. The channel is still open.
+    Timeout,
+
+    /// The channel is empty, and all senders have been dropped.
+    Disconnected,
+}
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            RecvTimeoutError::Timeout => "timed out waiting on channel",
+            RecvTimeoutError::Disconnected => "receiving on a closed channel",
+        };
+        msg.fmt(f)
+    }
+}
+
+impl std::error::Error for RecvTimeoutError {}
@@ -0,0 +1,446 @@
+//! A rendezvous channel, a channel without any capacity.
+//!
+//! Sending on a rendezvous channel does not complete before a receiver has taken the message.
+//! The sending thread blocks, or the sending task awaits, until then. This makes the send a
+//! handoff point, where the sender knows the receiver got the message once the send returns.
+//!
+//! Both [`Sender`] and [`Receiver`] can be cloned, making the channel mpmc. Every message is
+//! taken by exactly one receiver. Senders are served in the order they started sending.
+//! Without cloning, it is an spsc channel.
+//!
+//! Every operation that can wait exists in a blocking version, with and without a timeout, and
+//! in an async version. They can be mixed freely. A thread can send to an async task and the
+//! other way around, without involving any particular async runtime.
+//!
+//! ```rust
+//! use std::thread;
+//!
+//! let (sender, receiver) = oneshot::rendezvous::channel();
+//!
+//! let handle = thread::spawn(move || {
+//!     // Blocks until the main thread has received the message.
+//!     sender.send(5u32).unwrap();
+//! });
+//!
+//! assert_eq!(receiver.recv(), Ok(5));
+//! handle.join().unwrap();
+//! ```
+
+pub use super::recv_errors::{RecvError, RecvTimeoutError, TryRecvError};
+pub use super::send_errors::{SendError, SendTimeoutError};
+use super::waiters::{self, Token, Waiters};
+use super::ReceiverWaker;
+use alloc::collections::VecDeque;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task;
+use core::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(loom)]
+use loom::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+use std::sync::{Arc, Mutex};
+
+/// Creates a new rendezvous channel, returning the first sender and receiver of it.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        offers: VecDeque::new(),
+        senders: Waiters::default(),
+        receivers: Waiters::default(),
+        sender_count: 1,
+        receiver_count: 1,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    /// The messages of the senders currently sending, waiting to be taken by a receiver. In
+    /// the order the senders started sending. Each is tagged with the token of its sender.
+    offers: VecDeque<(Token, T)>,
+    /// Senders waiting for their offer to be taken, registered with the token of the offer.
+    senders: Waiters,
+    /// Receivers waiting for an offer.
+    receivers: Waiters,
+    sender_count: usize,
+    receiver_count: usize,
+}
+
+/// The sending half of a rendezvous channel. Can be cloned to send from many places.
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The receiving half of a rendezvous channel. Can be cloned to receive in many places.
+pub struct Receiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `message` on the channel, blocking the thread until a receiver has taken it.
+    ///
+    /// Returns an error containing the message if all receivers are dropped before one of
+    /// them took the message.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.send_until(message, None).map_err(|error| match error {
+            SendTimeoutError::Disconnected(message) => SendError(message),
+            SendTimeoutError::Timeout(_) => unreachable!("send without a deadline can't time out"),
+        })
+    }
+
+    /// Like [`Sender::send`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(())` if a receiver took the message before the timeout was reached.
+    ///  * `Err(Timeout(message))` if no receiver took the message before the timeout was
+    ///    reached.
+    ///  * `Err(Disconnected(message))` if all receivers were dropped.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking send operation.
+    pub fn send_timeout(&self, message: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(message, Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Sender::send`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(())` if a receiver took the message before the deadline was reached.
+    ///  * `Err(Timeout(message))` if no receiver took the message before the deadline was
+    ///    reached.
+    ///  * `Err(Disconnected(message))` if all receivers were dropped.
+    pub fn send_deadline(&self, message: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
+        self.send_until(message, Some(deadline))
+    }
+
+    /// Sends `message` on the channel, completing once a receiver has taken it. The async
+    /// version of [`Sender::send`].
+    ///
+    /// Dropping the returned future before it completes withdraws the message from the
+    /// channel, and drops it.
+    #[cfg(feature = "async")]
+    pub fn send_async(&self, message: T) -> SendFuture<'_, T> {
+        SendFuture {
+            sender: self,
+            message: Some(message),
+            token: None,
+        }
+    }
+
+    fn send_until(&self, message: T, deadline: Option<Instant>) -> Result<(), SendTimeoutError<T>> {
+        let (mut message, mut token) = (Some(message), None);
+        waiters::block(deadline, |waker| {
+            self.poll_send(&mut message, &mut token, waker)
+        })
+    }
+
+    /// Offers `message`, if it has not been offered yet, and checks if it was taken. Registers
+    /// `waker` if not. Without a waker the offer is withdrawn again. Once this returns `Ready`
+    /// the offer is gone, and `token` is cleared.
+    fn poll_send(
+        &self,
+        message: &mut Option<T>,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<(), SendTimeoutError<T>>> {
+        let mut shared = waiters::lock(&self.shared);
+        let mut receiver = None;
+        if let Some(message) = message.take() {
+            if shared.receiver_count == 0 {
+                return Poll::Ready(Err(SendTimeoutError::Disconnected(message)));
+            }
+            let offer = shared.senders.new_token();
+            shared.offers.push_back((offer, message));
+            *token = Some(offer);
+            receiver = shared.receivers.notify_one();
+        }
+
+        let offer = token.expect("send polled after completion");
+        let position = shared
+            .offers
+            .iter()
+            .position(|(sender, _)| *sender == offer);
+        let result = match position {
+            // A receiver took the offer, and removed our waker.
+            None => Poll::Ready(Ok(())),
+            Some(index) => match waker {
+                Some(waker) if shared.receiver_count > 0 => {
+                    shared.senders.register(token, waker);
+                    Poll::Pending
+                }
+                _ => {
+                    let (_, message) = shared.offers.remove(index).unwrap();
+                    shared.senders.take(offer);
+                    Poll::Ready(Err(if shared.receiver_count == 0 {
+                        SendTimeoutError::Disconnected(message)
+                    } else {
+                        SendTimeoutError::Timeout(message)
+                    }))
+                }
+            },
+        };
+        drop(shared);
+
+        if result.is_ready() {
+            *token = None;
+        }
+        if let Some(receiver) = receiver {
+            receiver.unpark();
+        }
+        result
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).sender_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.sender_count -= 1;
+        if shared.sender_count == 0 {
+            let receivers = shared.receivers.notify_all();
+            drop(shared);
+            waiters::wake_all(receivers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Checks if a sender is currently sending, and takes its message without blocking. Returns:
+    ///  * `Ok(message)` if a sender was waiting for its message to be taken.
+    ///  * `Err(Empty)` if no sender is currently sending, but senders are still alive.
+    ///  * `Err(Disconnected)` if all senders have been dropped.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut shared = waiters::lock(&self.shared);
+        match shared.offers.pop_front() {
+            Some((offer, message)) => {
+                let sender = shared.senders.take(offer);
+                drop(shared);
+                if let Some(sender) = sender {
+                    sender.unpark();
+                }
+                Ok(message)
+            }
+            None if shared.sender_count == 0 => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Blocks the thread until a sender sends a message, and takes it. Returns an error if all
+    /// senders are dropped while waiting.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(message)` if a sender sent a message before the timeout was reached.
+    ///  * `Err(Timeout)` if no message was sent before the timeout was reached.
+    ///  * `Err(Disconnected)` if all senders were dropped.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking receive operation.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(message)` if a sender sent a message before the deadline was reached.
+    ///  * `Err(Timeout)` if no message was sent before the deadline was reached.
+    ///  * `Err(Disconnected)` if all senders were dropped.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    /// Waits for a sender to send a message, and takes it. The async version of
+    /// [`Receiver::recv`].
+    ///
+    /// Dropping the returned future before it completes never loses a message. Messages are
+    /// only taken out of the channel by the poll that completes the future.
+    #[cfg(feature = "async")]
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            token: None,
+        }
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut token = None;
+        waiters::block(deadline, |waker| self.poll_recv(&mut token, waker))
+    }
+
+    /// Takes the first offer, if there is one. Registers `waker` if not. Without a waker, gives
+    /// up waiting. Once this returns `Ready`, the receiver is not waiting anymore.
+    fn poll_recv(
+        &self,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<T, RecvTimeoutError>> {
+        let mut shared = waiters::lock(&self.shared);
+        let (result, sender) = match shared.offers.pop_front() {
+            Some((offer, message)) => (Poll::Ready(Ok(message)), shared.senders.take(offer)),
+            None if shared.sender_count == 0 => {
+                (Poll::Ready(Err(RecvTimeoutError::Disconnected)), None)
+            }
+            None => match waker {
+                Some(waker) => {
+                    shared.receivers.register(token, waker);
+                    (Poll::Pending, None)
+                }
+                None => (Poll::Ready(Err(RecvTimeoutError::Timeout)), None),
+            },
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                shared.receivers.take(token);
+            }
+        }
+        drop(shared);
+
+        if let Some(sender) = sender {
+            sender.unpark();
+        }
+        result
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        shared.receiver_count -= 1;
+        if shared.receiver_count == 0 {
+            // The senders withdraw their offers when they see there is no receiver left.
+            let senders = shared.senders.notify_all();
+            drop(shared);
+            waiters::wake_all(senders);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+/// A future sending a message on a rendezvous channel. Created by [`Sender::send_async`].
+#[cfg(feature = "async")]
+pub struct SendFuture<'a, T> {
+    sender: &'a Sender<T>,
+    message: Option<T>,
+    token: Option<Token>,
+}
+
+// The message is never pinned, it is moved into the channel when first polled.
+#[cfg(feature = "async")]
+impl<T> Unpin for SendFuture<'_, T> {}
+
+#[cfg(feature = "async")]
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), SendError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.sender
+            .poll_send(
+                &mut this.message,
+                &mut this.token,
+                Some(ReceiverWaker::task_waker(cx)),
+            )
+            .map_err(|error| SendError(error.into_inner()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        if let Some(offer) = self.token {
+            let mut shared = waiters::lock(&self.sender.shared);
+            shared.senders.take(offer);
+            let position = shared
+                .offers
+                .iter()
+                .position(|(sender, _)| *sender == offer);
+            let message = position.and_then(|index| shared.offers.remove(index));
+            // Drop the withdrawn message outside the lock.
+            drop(shared);
+            drop(message);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for SendFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendFuture").finish_non_exhaustive()
+    }
+}
+
+/// A future receiving a message from a rendezvous channel. Created by
+/// [`Receiver::recv_async`].
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+    token: Option<Token>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.receiver
+            .poll_recv(&mut this.token, Some(ReceiverWaker::task_waker(cx)))
+            .map_err(|_| RecvError)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        if self.token.is_some() {
+            let mut shared = waiters::lock(&self.receiver.shared);
+            let ready = !shared.offers.is_empty();
+            let receiver = shared.receivers.cancel(self.token, ready);
+            drop(shared);
+            if let Some(receiver) = receiver {
+                receiver.unpark();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for RecvFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvFuture").finish_non_exhaustive()
+    }
+}
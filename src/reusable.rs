@@ -0,0 +1,120 @@
+use super::{allocate, dealloc, endpoints, origins::*, Channel, Receiver, Sender};
+use allocator_api2::alloc::Global;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+#[cfg(not(loom))]
+use core::sync::atomic::Ordering::SeqCst;
+#[cfg(loom)]
+use loom::sync::atomic::Ordering::SeqCst;
+
+/// A oneshot channel allocation that can be reused for many consecutive message transfers.
+///
+/// Every call to [`channel`] allocates a new channel on the heap. A `ReusableChannel` instead
+/// allocates once, and every call to [`ReusableChannel::channel`] hands out a new
+/// [`Sender`]/[`Receiver`] pair backed by that same allocation. The endpoints are the same
+/// types as the ones returned from [`channel`] and behave identically.
+///
+/// A new pair can only be created once both endpoints of the previous pair are gone. I.e. the
+/// message has been received (or the receiver dropped) and the sender has been consumed or
+/// dropped. Until then the old endpoints are still using the channel and no new pair is handed
+/// out. That makes it impossible for an endpoint from an earlier round to interfere with a later
+/// one. For the same reason the endpoints don't carry a generation counter: there is never a
+/// stale endpoint around that one would have to reject.
+///
+/// ```rust
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() {}
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// let mut reusable = oneshot::ReusableChannel::new();
+/// for i in 0..3 {
+///     let (sender, receiver) = reusable.channel().expect("Previous round is done");
+///     std::thread::spawn(move || sender.send(i));
+///     assert_eq!(receiver.recv(), Ok(i));
+/// }
+/// # }
+/// ```
+///
+/// If the `ReusableChannel` is dropped while a pair of endpoints is still alive, the last one
+/// of them frees the allocation, just like for a channel created by [`channel`].
+///
+/// [`channel`]: crate::channel
+pub struct ReusableChannel<T> {
+    channel_ptr: NonNull<Channel<T>>,
+    // See SendError for details
+    _dropck: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for ReusableChannel<T> {}
+unsafe impl<T: Send> Sync for ReusableChannel<T> {}
+
+impl<T> ReusableChannel<T> {
+    /// Allocates a new channel that can be used for any number of consecutive oneshot transfers.
+    pub fn new() -> Self {
+        Self {
+            channel_ptr: allocate(Channel::new(SLOT_FREE), Global),
+            _dropck: PhantomData,
+        }
+    }
+
+    /// Returns a new pair of endpoints backed by this channel's allocation. Returns `None` if
+    /// the endpoints from the previous call are still in use.
+    pub fn channel(&mut self) -> Option<(Sender<T>, Receiver<T>)> {
+        // SAFETY: We own the channel, and it is not freed while we are alive.
+        let channel = unsafe { self.channel_ptr.as_ref() };
+
+        match channel.origin.load(SeqCst) {
+            // All endpoints are gone. They leave the channel without any message or waker in it.
+            SLOT_FREE => {
+                channel.reset();
+                channel.origin.store(SLOT_IN_USE, SeqCst);
+                // SAFETY: The channel was just reset, and no endpoints are using it.
+                Some(unsafe { endpoints(self.channel_ptr) })
+            }
+            // Some endpoint from the last pair is still alive.
+            SLOT_IN_USE => None,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns true if endpoints returned from [`ReusableChannel::channel`] are still alive.
+    /// If this returns false, the next call to [`ReusableChannel::channel`] will succeed.
+    pub fn is_in_use(&self) -> bool {
+        // SAFETY: We own the channel, and it is not freed while we are alive.
+        let channel = unsafe { self.channel_ptr.as_ref() };
+
+        channel.origin.load(SeqCst) == SLOT_IN_USE
+    }
+}
+
+impl<T> Default for ReusableChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ReusableChannel<T> {
+    fn drop(&mut self) {
+        // SAFETY: The reference won't be used after the channel is freed in this method
+        let channel = unsafe { self.channel_ptr.as_ref() };
+
+        // Turn the channel into a normal heap channel, so the last endpoint frees it.
+        match channel.origin.swap(HEAP, SeqCst) {
+            // No endpoints are alive. We are responsible for freeing the channel.
+            SLOT_FREE => unsafe { dealloc::<T, Global>(self.channel_ptr) },
+            // Endpoints are alive. The last one will free the channel.
+            SLOT_IN_USE => (),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ReusableChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReusableChannel")
+            .field("in_use", &self.is_in_use())
+            .finish()
+    }
+}
@@ -0,0 +1,249 @@
+use super::{
+    states::*, thread, Acquire, Allocator, Channel, Receiver, ReceiverWaker, RecvError,
+    RecvTimeoutError,
+};
+use std::time::{Duration, Instant};
+
+/// Blocks the current thread until one of the `receivers` is ready, and receives from it.
+/// A receiver is ready when its [`Sender`](crate::Sender) has sent the message or been dropped.
+///
+/// Returns the index of the ready receiver in `receivers`, together with the result of
+/// receiving from it. Exactly like [`Receiver::recv_ref`], this is `Ok(message)` if the
+/// message was sent, and `Err(RecvError::SenderDropped)` if the sender was dropped without
+/// sending anything or if the message has already been extracted by a previous receive call, or
+/// `Err(RecvError::SenderPanicked)` if it was dropped during a panic. If more than one receiver
+/// is ready, the one with the lowest index is picked.
+///
+/// The other receivers are left untouched. Any message that arrives on them while waiting stays
+/// in their channel, and can be received later.
+///
+/// It is fine to call this function with receivers that have been polled asynchronously.
+///
+/// # Panics
+///
+/// Panics if `receivers` is empty, since this would block forever.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::thread;
+/// let (sender1, receiver1) = oneshot::channel::<u32>();
+/// let (sender2, receiver2) = oneshot::channel::<u32>();
+///
+/// thread::spawn(move || {
+///     sender2.send(2).unwrap();
+/// });
+///
+/// let (index, result) = oneshot::select(&[&receiver1, &receiver2]);
+/// assert_eq!(index, 1);
+/// assert_eq!(result, Ok(2));
+/// # drop(sender1);
+/// ```
+pub fn select<T, A: Allocator>(receivers: &[&Receiver<T, A>]) -> (usize, Result<T, RecvError>) {
+    assert!(!receivers.is_empty(), "select called without any receivers");
+    // SAFETY: The channels will not be freed while the receivers are borrowed.
+    unsafe { select_until(receivers, None) }.expect("select without a deadline can't time out")
+}
+
+/// Like [`select`], but will not block longer than `timeout`. Returns `None` if none of the
+/// receivers became ready before the timeout was reached.
+///
+/// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+/// in the future this falls back to an indefinitely blocking [`select`].
+pub fn select_timeout<T, A: Allocator>(
+    receivers: &[&Receiver<T, A>],
+    timeout: Duration,
+) -> Option<(usize, Result<T, RecvError>)> {
+    match Instant::now().checked_add(timeout) {
+        Some(deadline) => select_deadline(receivers, deadline),
+        None => Some(select(receivers)),
+    }
+}
+
+/// Like [`select`], but will not block longer than until `deadline`. Returns `None` if none of
+/// the receivers became ready before the deadline was reached.
+///
+/// Unlike [`select`], this accepts an empty slice of receivers. It then blocks until the
+/// deadline and returns `None`.
+pub fn select_deadline<T, A: Allocator>(
+    receivers: &[&Receiver<T, A>],
+    deadline: Instant,
+) -> Option<(usize, Result<T, RecvError>)> {
+    // SAFETY: The channels will not be freed while the receivers are borrowed.
+    unsafe { select_until(receivers, Some(deadline)) }
+}
+
+/// Blocks the current thread until all of the `receivers` are ready, or until `deadline` has
+/// passed, and receives from all of them. Returns the results in the same order as `receivers`:
+///  * `Ok(message)` if there was a message in the channel before the deadline was reached.
+///  * `Err(Timeout)` if no message arrived on the channel before the deadline was reached.
+///  * `Err(SenderDropped)` if the sender was dropped before sending anything or if the message
+///    has already been extracted by a previous receive call.
+///  * `Err(SenderPanicked)` if the sender was dropped during a panic before sending anything.
+///
+/// This is equivalent to calling [`Receiver::recv_deadline`] on each receiver in turn, but the
+/// thread is only woken up when one of the senders acts.
+///
+/// It is fine to call this function with receivers that have been polled asynchronously.
+///
+/// # Examples
+///
+/// ```rust
+/// # use std::thread;
+/// # use std::time::{Duration, Instant};
+/// let receivers: Vec<_> = (0..4u32)
+///     .map(|i| {
+///         let (sender, receiver) = oneshot::channel();
+///         thread::spawn(move || sender.send(i * 10));
+///         receiver
+///     })
+///     .collect();
+///
+/// let deadline = Instant::now() + Duration::from_secs(10);
+/// let receivers: Vec<_> = receivers.iter().collect();
+/// let results = oneshot::gather(&receivers, deadline);
+/// assert_eq!(results, [Ok(0), Ok(10), Ok(20), Ok(30)]);
+/// ```
+pub fn gather<T, A: Allocator>(
+    receivers: &[&Receiver<T, A>],
+    deadline: Instant,
+) -> Vec<Result<T, RecvTimeoutError>> {
+    // SAFETY: The channels will not be freed while the receivers are borrowed.
+    let channel = |index: usize| unsafe { receivers[index].channel_ptr.as_ref() };
+    let receive = |index: usize, state: State| {
+        // SAFETY: Only called with a state just observed on the channel.
+        unsafe { channel(index).recv_ref(state) }
+    };
+    let mut results: Vec<Option<Result<T, RecvTimeoutError>>> =
+        (0..receivers.len()).map(|_| None).collect();
+    let mut pending = receivers.len();
+
+    // All channels get a waker for the same thread, so each sender acting unparks us. A
+    // sender consumes the waker when it acts, so completed channels need no deregistration.
+    let thread = thread::current();
+    for (index, result) in results.iter_mut().enumerate() {
+        match unsafe { channel(index).start_waiting(|| ReceiverWaker::thread(thread.clone())) } {
+            RECEIVING => (),
+            state => {
+                *result = Some(receive(index, state));
+                pending -= 1;
+            }
+        }
+    }
+
+    while pending > 0 {
+        match deadline.checked_duration_since(Instant::now()) {
+            Some(timeout) => thread::park_timeout(timeout),
+            // We reached the deadline. Unregister from the channels still waiting. A sender
+            // might have acted just before we did so, in which case we still take its result.
+            None => {
+                for (index, result) in results.iter_mut().enumerate() {
+                    if result.is_none() {
+                        *result = Some(match unsafe { channel(index).stop_receiving(RECEIVING) } {
+                            EMPTY => Err(RecvTimeoutError::Timeout),
+                            state => receive(index, state),
+                        });
+                    }
+                }
+                break;
+            }
+        }
+
+        // The wakeup might be spurious, or one of many senders acting. So check all channels
+        // still waiting. A channel in the UNPARKING state unparks us again after setting the
+        // final state.
+        for (index, result) in results.iter_mut().enumerate() {
+            if result.is_none() {
+                if let state @ (MESSAGE | DISCONNECTED | PANICKED) =
+                    channel(index).state.load(Acquire)
+                {
+                    *result = Some(receive(index, state));
+                    pending -= 1;
+                }
+            }
+        }
+    }
+
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+/// Registers a waker for the current thread in all the channels and parks until one of them is
+/// ready or the deadline has passed. No waker is left in any of the channels when this returns.
+///
+/// # Safety
+///
+/// The channels of all `receivers` must be alive for the duration of the call.
+unsafe fn select_until<T, A: Allocator>(
+    receivers: &[&Receiver<T, A>],
+    deadline: Option<Instant>,
+) -> Option<(usize, Result<T, RecvError>)> {
+    let channel = |index: usize| receivers[index].channel_ptr.as_ref();
+
+    // All channels get a waker for the same thread, so whichever sender acts first unparks us.
+    let thread = thread::current();
+    for index in 0..receivers.len() {
+        match channel(index).start_waiting(|| ReceiverWaker::thread(thread.clone())) {
+            RECEIVING => (),
+            // This channel is already ready. Unregister from the ones we already registered in.
+            state => {
+                deregister((0..index).map(channel));
+                return Some((index, take(channel(index), state)));
+            }
+        }
+    }
+
+    loop {
+        match deadline.map(|deadline| deadline.checked_duration_since(Instant::now())) {
+            None => thread::park(),
+            Some(Some(timeout)) => thread::park_timeout(timeout),
+            // We reached the deadline. Unregister from all channels. A sender might have acted
+            // just before we did so, in which case we still return its result.
+            Some(None) => {
+                let mut ready = None;
+                for index in 0..receivers.len() {
+                    let state = channel(index).stop_receiving(RECEIVING);
+                    if ready.is_none() && state != EMPTY {
+                        ready = Some((index, state));
+                    }
+                }
+                return ready.map(|(index, state)| (index, take(channel(index), state)));
+            }
+        }
+
+        // Parking can wake up spuriously, and earlier selects on the same thread can leave
+        // an unpark token behind. So we have to check all channels after each wakeup. A channel
+        // in the UNPARKING state unparks us again after setting the final state.
+        let ready =
+            (0..receivers.len()).find_map(|index| match channel(index).state.load(Acquire) {
+                state @ (MESSAGE | DISCONNECTED | PANICKED) => Some((index, state)),
+                _ => None,
+            });
+        if let Some((index, state)) = ready {
+            deregister(
+                (0..receivers.len())
+                    .filter(|&other| other != index)
+                    .map(channel),
+            );
+            return Some((index, take(channel(index), state)));
+        }
+    }
+}
+
+/// Makes sure none of the channels hold our waker anymore. Any message that arrived in them
+/// is left there.
+unsafe fn deregister<'a, T: 'a>(channels: impl Iterator<Item = &'a Channel<T>>) {
+    for channel in channels {
+        channel.stop_receiving(channel.state.load(Acquire));
+    }
+}
+
+/// Receives from a channel observed to be in the MESSAGE, DISCONNECTED or PANICKED `state`.
+unsafe fn take<T>(channel: &Channel<T>, state: State) -> Result<T, RecvError> {
+    match state {
+        // The sender sent the message. We take the message and mark the channel disconnected.
+        MESSAGE => Ok(channel.receive_message()),
+        DISCONNECTED => Err(RecvError::SenderDropped),
+        PANICKED => Err(RecvError::SenderPanicked),
+        _ => unreachable!(),
+    }
+}
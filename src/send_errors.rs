@@ -0,0 +1,129 @@
+//! Send errors of the channels carrying more than one message. Unlike the oneshot
+//! [`SendError`](crate::SendError) they simply own the message, since there is no channel
+//! allocation to hand over.
+
+use core::fmt;
+
+/// An error returned when sending on a channel whose receivers have all been dropped. Contains
+/// the message that could not be sent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SendError<T>(pub T);
+
+impl<T> SendError<T> {
+    /// Consumes the error and returns the message that failed to be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "sending on a closed channel".fmt(f)
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SendError<{}>(_)", stringify!(T))
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+/// An error returned from a time limited blocking send. Contains the message that could not
+/// be sent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SendTimeoutError<T> {
+    /// The message could not be sent before the timeout was reached. The channel is still
+    /// open.
+    Timeout(T),
+
+    /// All receivers have been dropped.
+    Disconnected(T),
+}
+
+impl<T> SendTimeoutError<T> {
+    /// Consumes the error and returns the message that failed to be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            SendTimeoutError::Timeout(message) | SendTimeoutError::Disconnected(message) => message,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for SendTimeoutError<T> {
+    fn from(error: SendError<T>) -> Self {
+        SendTimeoutError::Disconnected(error.0)
+    }
+}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            SendTimeoutError::Timeout(_) => "timed out sending on channel",
+            SendTimeoutError::Disconnected(_) => "sending on a closed channel",
+        };
+        msg.fmt(f)
+    }
+}
+
+impl<T> fmt::Debug for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SendTimeoutError::Timeout(_) => write!(f, "Timeout<{}>(_)", stringify!(T)),
+            SendTimeoutError::Disconnected(_) => write!(f, "Disconnected<{}>(_)", stringify!(T)),
+        }
+    }
+}
+
+impl<T> std::error::Error for SendTimeoutError<T> {}
+
+/// An error returned when trying a non blocking send. Contains the message that could not be
+/// sent.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TrySendError<T> {
+    /// The channel is full. The channel is still open.
+    Full(T),
+
+    /// All receivers have been dropped.
+    Disconnected(T),
+}
+
+impl<T> TrySendError<T> {
+    /// Consumes the error and returns the message that failed to be sent.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(message) | TrySendError::Disconnected(message) => message,
+        }
+    }
+}
+
+impl<T> From<SendError<T>> for TrySendError<T> {
+    fn from(error: SendError<T>) -> Self {
+        TrySendError::Disconnected(error.0)
+    }
+}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            TrySendError::Full(_) => "sending on a full channel",
+            TrySendError::Disconnected(_) => "sending on a closed channel",
+        };
+        msg.fmt(f)
+    }
+}
+
+impl<T> fmt::Debug for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "Full<{}>(_)", stringify!(T)),
+            TrySendError::Disconnected(_) => write!(f, "Disconnected<{}>(_)", stringify!(T)),
+        }
+    }
+}
+
+impl<T> std::error::Error for TrySendError<T> {}
@@ -0,0 +1,285 @@
+//! A oneshot channel whose receiver can be cloned. Every clone gets a clone of the message.
+//!
+//! Useful when a value is computed once and many threads or tasks wait for it, like a loaded
+//! configuration or a shutdown signal. Create one with [`shared_channel`].
+//!
+//! Unlike the [`Receiver`](crate::Receiver) of a regular oneshot channel, which has a single
+//! waker slot in the channel, any number of [`SharedReceiver`]s can wait at the same time. They
+//! register their wakers in a queue behind a mutex in the channel, and are all woken up when the
+//! message is sent or the [`Sender`] is dropped.
+//!
+//! ```rust
+//! use std::thread;
+//!
+//! let (sender, receiver) = oneshot::shared_channel();
+//!
+//! let handles: Vec<_> = (0..4)
+//!     .map(|_| {
+//!         let receiver = receiver.clone();
+//!         thread::spawn(move || receiver.recv())
+//!     })
+//!     .collect();
+//!
+//! sender.send("configuration").unwrap();
+//! for handle in handles {
+//!     assert_eq!(handle.join().unwrap(), Ok("configuration"));
+//! }
+//! // The message stays in the channel, for every receiver to see.
+//! assert_eq!(receiver.try_recv(), Ok("configuration"));
+//! ```
+
+pub use super::send_errors::SendError;
+use super::waiters::{self, Token, Waiters};
+use super::{ReceiverWaker, RecvError, RecvTimeoutError, TryRecvError};
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task;
+use core::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(loom)]
+use loom::sync::{Arc, Mutex};
+#[cfg(not(loom))]
+use std::sync::{Arc, Mutex};
+
+/// Creates a new oneshot channel whose receiver can be cloned, and returns the two endpoints,
+/// [`Sender`] and [`SharedReceiver`].
+///
+/// Every receiver gets a clone of the message, as often as it asks for it.
+pub fn shared_channel<T: Clone>() -> (Sender<T>, SharedReceiver<T>) {
+    let shared = Arc::new(Mutex::new(Shared {
+        message: Message::Empty,
+        receivers: Waiters::default(),
+        receiver_count: 1,
+    }));
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        SharedReceiver {
+            shared,
+            #[cfg(feature = "async")]
+            token: None,
+        },
+    )
+}
+
+struct Shared<T> {
+    message: Message<T>,
+    /// Receivers waiting for the message to be sent.
+    receivers: Waiters,
+    receiver_count: usize,
+}
+
+enum Message<T> {
+    /// The sender is alive and has not sent anything yet.
+    Empty,
+    Sent(T),
+    /// The sender was dropped without sending anything, or all receivers were dropped.
+    Disconnected,
+    /// The sender was dropped without sending anything while its thread was panicking.
+    Panicked,
+}
+
+/// The sending half of a shared oneshot channel. Created by
+/// [`shared_channel`].
+pub struct Sender<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// The receiving half of a shared oneshot channel. Created by
+/// [`shared_channel`]. Can be cloned to wait for the message in many
+/// places.
+///
+/// Receiving does not take the message out of the channel, every receive returns a clone of it.
+pub struct SharedReceiver<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    /// The place of this receiver in the waiters, when polled as a `Future`.
+    #[cfg(feature = "async")]
+    token: Option<Token>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `message` to all [`SharedReceiver`]s, current and future clones alike.
+    ///
+    /// Returns an error containing the message if all receivers have already been dropped.
+    pub fn send(self, message: T) -> Result<(), SendError<T>> {
+        let mut shared = waiters::lock(&self.shared);
+        if shared.receiver_count == 0 {
+            return Err(SendError(message));
+        }
+        shared.message = Message::Sent(message);
+        let receivers = shared.receivers.notify_all();
+        drop(shared);
+
+        waiters::wake_all(receivers);
+        Ok(())
+    }
+
+    /// Returns true if all [`SharedReceiver`]s have been dropped.
+    ///
+    /// If this returns true, any future call to [`Sender::send`] is guaranteed to return an error.
+    /// If it returns false, the receivers might still be dropped before the message is sent.
+    pub fn is_closed(&self) -> bool {
+        waiters::lock(&self.shared).receiver_count == 0
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        if let Message::Empty = shared.message {
+            shared.message = if std::thread::panicking() {
+                Message::Panicked
+            } else {
+                Message::Disconnected
+            };
+            let receivers = shared.receivers.notify_all();
+            drop(shared);
+            waiters::wake_all(receivers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T: Clone> SharedReceiver<T> {
+    /// Checks if the message has been sent, without blocking. Returns:
+    ///  * `Ok(message)` with a clone of the message if it has been sent.
+    ///  * `Err(Empty)` if the [`Sender`] is alive, but has not yet sent the message.
+    ///  * `Err(SenderDropped)` if the [`Sender`] was dropped without sending anything.
+    ///  * `Err(SenderPanicked)` if the [`Sender`] was dropped during a panic before sending
+    ///    anything.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.poll_recv(&mut None, None) {
+            Poll::Ready(Ok(message)) => Ok(message),
+            Poll::Ready(Err(RecvTimeoutError::Timeout)) => Err(TryRecvError::Empty),
+            Poll::Ready(Err(RecvTimeoutError::SenderDropped)) => Err(TryRecvError::SenderDropped),
+            Poll::Ready(Err(RecvTimeoutError::SenderPanicked)) => Err(TryRecvError::SenderPanicked),
+            Poll::Pending => unreachable!("recv polled without a waker must complete"),
+        }
+    }
+
+    /// Blocks the thread until the message has been sent, and returns a clone of it. Returns an
+    /// error if the [`Sender`] was dropped without sending anything, telling whether it was
+    /// dropped during a panic.
+    ///
+    /// It is fine to call this method after this receiver has been polled asynchronously.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None)
+            .map_err(RecvTimeoutError::into_recv_error)
+    }
+
+    /// Like [`SharedReceiver::recv`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(message)` if the message was sent before the timeout was reached.
+    ///  * `Err(Timeout)` if no message was sent before the timeout was reached.
+    ///  * `Err(SenderDropped)` if the [`Sender`] was dropped without sending anything.
+    ///  * `Err(SenderPanicked)` if the [`Sender`] was dropped during a panic before sending
+    ///    anything.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking receive operation.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`SharedReceiver::recv`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(message)` if the message was sent before the deadline was reached.
+    ///  * `Err(Timeout)` if no message was sent before the deadline was reached.
+    ///  * `Err(SenderDropped)` if the [`Sender`] was dropped without sending anything.
+    ///  * `Err(SenderPanicked)` if the [`Sender`] was dropped during a panic before sending
+    ///    anything.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut token = None;
+        waiters::block(deadline, |waker| self.poll_recv(&mut token, waker))
+    }
+
+    /// Clones the message if it has been sent. Registers `waker` if not. Without a waker, gives
+    /// up waiting. Once this returns `Ready`, the receiver is not waiting anymore.
+    fn poll_recv(
+        &self,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<T, RecvTimeoutError>> {
+        let mut shared = waiters::lock(&self.shared);
+        let result = match &shared.message {
+            Message::Sent(message) => Poll::Ready(Ok(message.clone())),
+            Message::Disconnected => Poll::Ready(Err(RecvTimeoutError::SenderDropped)),
+            Message::Panicked => Poll::Ready(Err(RecvTimeoutError::SenderPanicked)),
+            Message::Empty => match waker {
+                Some(waker) => {
+                    shared.receivers.register(token, waker);
+                    Poll::Pending
+                }
+                None => Poll::Ready(Err(RecvTimeoutError::Timeout)),
+            },
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                shared.receivers.take(token);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone> Future for SharedReceiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut token = this.token.take();
+        let result = this.poll_recv(&mut token, Some(ReceiverWaker::task_waker(cx)));
+        this.token = token;
+        result.map_err(RecvTimeoutError::into_recv_error)
+    }
+}
+
+impl<T> Clone for SharedReceiver<T> {
+    fn clone(&self) -> Self {
+        waiters::lock(&self.shared).receiver_count += 1;
+        Self {
+            shared: self.shared.clone(),
+            #[cfg(feature = "async")]
+            token: None,
+        }
+    }
+}
+
+impl<T> Drop for SharedReceiver<T> {
+    fn drop(&mut self) {
+        let mut shared = waiters::lock(&self.shared);
+        // Every waiting receiver is woken up by the same event, so there is no notification to
+        // hand on.
+        #[cfg(feature = "async")]
+        if let Some(token) = self.token {
+            shared.receivers.take(token);
+        }
+        shared.receiver_count -= 1;
+        if shared.receiver_count == 0 {
+            // Nobody can receive the message anymore. Drop it outside the lock.
+            let message = core::mem::replace(&mut shared.message, Message::Disconnected);
+            drop(shared);
+            drop(message);
+        }
+    }
+}
+
+impl<T> fmt::Debug for SharedReceiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedReceiver").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,432 @@
+//! Oneshot channels that live in a `static` or on the stack and never allocate.
+//!
+//! A [`StaticChannel`] holds all the state of a single oneshot channel inline. It hands out
+//! [`Sender`] and [`Receiver`] endpoints borrowing it, that work just like the heap allocated
+//! ones at the crate root. This module is available without the `alloc` feature, making it
+//! usable on targets without a heap. For example to let an interrupt handler signal a result
+//! to a task.
+//!
+//! Once both endpoints are gone the channel can hand out a new pair, so a single
+//! `StaticChannel` can be used for any number of consecutive message transfers.
+//!
+//! ```rust
+//! use oneshot::StaticChannel;
+//!
+//! static CHANNEL: StaticChannel<u32> = StaticChannel::new();
+//!
+//! let (sender, receiver) = CHANNEL.channel().expect("Channel is not in use");
+//! assert!(CHANNEL.channel().is_none());
+//!
+//! sender.send(5).unwrap();
+//! assert_eq!(receiver.try_recv(), Ok(5));
+//! drop(receiver);
+//!
+//! // Both endpoints are gone, so the channel can be used again.
+//! assert!(CHANNEL.channel().is_some());
+//! ```
+
+#[cfg(feature = "std")]
+use super::RecvStrategy;
+use super::{origins::*, Channel, ChannelState, TryRecvError};
+#[cfg(feature = "park")]
+use super::{Clock, Park, RecvError, RecvTimeoutError};
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+
+#[cfg(not(loom))]
+use core::sync::atomic::Ordering::SeqCst;
+#[cfg(loom)]
+use loom::sync::atomic::Ordering::SeqCst;
+
+#[cfg(feature = "async")]
+use core::{
+    pin::Pin,
+    task::{self, Poll},
+};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+/// A oneshot channel that does not allocate. See the [module level documentation] for details.
+///
+/// [module level documentation]: self
+pub struct StaticChannel<T> {
+    channel: Channel<T>,
+}
+
+unsafe impl<T: Send> Send for StaticChannel<T> {}
+unsafe impl<T: Send> Sync for StaticChannel<T> {}
+
+impl<T> StaticChannel<T> {
+    /// Creates a new channel, not yet used by any endpoints.
+    #[cfg(not(loom))]
+    pub const fn new() -> Self {
+        Self {
+            channel: Channel::new(SLOT_FREE),
+        }
+    }
+
+    /// Creates a new channel, not yet used by any endpoints.
+    #[cfg(loom)]
+    pub fn new() -> Self {
+        Self {
+            channel: Channel::new(SLOT_FREE),
+        }
+    }
+
+    /// Returns a new pair of endpoints for this channel. Returns `None` if endpoints returned
+    /// from an earlier call are still in use.
+    pub fn channel(&self) -> Option<(Sender<'_, T>, Receiver<'_, T>)> {
+        // Claim the channel. Only one caller can succeed, and only after both endpoints of the
+        // previous pair are gone. They leave the channel without any message or waker in it.
+        self.channel
+            .origin
+            .compare_exchange(SLOT_FREE, SLOT_IN_USE, SeqCst, SeqCst)
+            .ok()?;
+        self.channel.reset();
+
+        Some((
+            Sender {
+                channel: &self.channel,
+                _invariant: PhantomData,
+            },
+            Receiver {
+                channel: &self.channel,
+            },
+        ))
+    }
+
+    /// Returns true if endpoints returned from [`StaticChannel::channel`] are still alive.
+    /// If this returns false, the next call to [`StaticChannel::channel`] will succeed, unless
+    /// another thread calls it first.
+    pub fn is_in_use(&self) -> bool {
+        self.channel.origin.load(SeqCst) == SLOT_IN_USE
+    }
+}
+
+impl<T> Default for StaticChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for StaticChannel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticChannel")
+            .field("in_use", &self.is_in_use())
+            .finish()
+    }
+}
+
+/// Called by the last endpoint to go away. Lets the `StaticChannel` hand out new endpoints.
+fn release<T>(channel: &Channel<T>) {
+    channel.origin.store(SLOT_FREE, SeqCst);
+}
+
+/// The sending endpoint of a [`StaticChannel`].
+///
+/// Works like the heap allocated [`Sender`](crate::Sender).
+pub struct Sender<'a, T> {
+    channel: &'a Channel<T>,
+    // See the heap allocated Sender for why this has to be invariant.
+    _invariant: PhantomData<fn(T) -> T>,
+}
+
+/// The receiving endpoint of a [`StaticChannel`].
+///
+/// Works like the heap allocated [`Receiver`](crate::Receiver).
+pub struct Receiver<'a, T> {
+    channel: &'a Channel<T>,
+}
+
+unsafe impl<T: Send> Send for Sender<'_, T> {}
+unsafe impl<T: Send> Send for Receiver<'_, T> {}
+impl<T> Unpin for Receiver<'_, T> {}
+
+impl<'a, T> Sender<'a, T> {
+    /// Sends `message` over the channel to the corresponding [`Receiver`].
+    ///
+    /// Returns an error if the receiver has already been dropped. The message can
+    /// be extracted from the error.
+    ///
+    /// This method is lock-free and wait-free unless it has to wake up a waiting receiver.
+    pub fn send(self, message: T) -> Result<(), SendError<'a, T>> {
+        let channel = self.channel;
+
+        // Don't run our Drop implementation if send was called, any cleanup now happens here
+        mem::forget(self);
+
+        match unsafe { channel.send(message) } {
+            Ok(()) => Ok(()),
+            // The receiver was already dropped. The error is responsible for releasing the
+            // channel.
+            Err(()) => Err(SendError { channel }),
+        }
+    }
+
+    /// Returns true if the associated [`Receiver`] has been dropped.
+    pub fn is_closed(&self) -> bool {
+        self.channel.is_closed()
+    }
+
+    /// Returns a snapshot of the channel state. See [`crate::Sender::state`].
+    pub fn state(&self) -> ChannelState {
+        self.channel.channel_state()
+    }
+
+    /// Blocks the current thread until the associated [`Receiver`] has been dropped.
+    #[cfg(feature = "std")]
+    pub fn wait_closed(&self) {
+        unsafe { self.channel.wait_closed(None) };
+    }
+
+    /// Like [`Sender::wait_closed`], but will not block longer than `timeout`. Returns true
+    /// if the [`Receiver`] was dropped before the timeout was reached, false otherwise.
+    #[cfg(feature = "std")]
+    pub fn wait_closed_timeout(&self, timeout: Duration) -> bool {
+        unsafe {
+            self.channel
+                .wait_closed(Instant::now().checked_add(timeout))
+        }
+    }
+
+    /// Returns a future that completes when the associated [`Receiver`] has been dropped.
+    #[cfg(feature = "async")]
+    pub fn closed(&mut self) -> Closed<'_, 'a, T> {
+        Closed { sender: self }
+    }
+
+    /// Polls whether the associated [`Receiver`] has been dropped. Returns `Poll::Ready(())` if it
+    /// has, otherwise registers the task in `cx` to be woken up when it happens.
+    #[cfg(feature = "async")]
+    pub fn poll_closed(&mut self, cx: &mut task::Context<'_>) -> Poll<()> {
+        unsafe { self.channel.poll_closed(cx) }
+    }
+}
+
+impl<T> Drop for Sender<'_, T> {
+    fn drop(&mut self) {
+        if unsafe { self.channel.drop_sender() } {
+            // The receiver was already dropped. We are the last endpoint.
+            release(self.channel);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T> Receiver<'a, T> {
+    /// Checks if there is a message in the channel without blocking. Returns:
+    ///  * `Ok(message)` if there was a message in the channel.
+    ///  * `Err(Empty)` if the [`Sender`] is alive, but has not yet sent a message.
+    ///  * `Err(SenderDropped)` if the [`Sender`] was dropped before sending anything or if the
+    ///    message has already been extracted by a previous receive call.
+    ///  * `Err(SenderPanicked)` if the [`Sender`] was dropped before sending anything, while its
+    ///    thread was panicking.
+    ///
+    /// This method is completely lock-free and wait-free.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        unsafe { self.channel.try_recv() }
+    }
+
+    /// Returns a snapshot of the channel state. See [`crate::Receiver::state`].
+    pub fn state(&self) -> ChannelState {
+        self.channel.channel_state()
+    }
+
+    /// Returns true if there is a message in the channel. See [`crate::Receiver::has_message`].
+    pub fn has_message(&self) -> bool {
+        self.state() == ChannelState::Message
+    }
+
+    /// Returns true if the channel is closed. See [`crate::Receiver::is_closed`].
+    pub fn is_closed(&self) -> bool {
+        self.state() == ChannelState::Disconnected
+    }
+
+    /// Closes the channel from the receiving side and returns the message, if one was sent and
+    /// not yet received. Like dropping the receiver, but without dropping the message.
+    pub fn close(self) -> Option<T> {
+        let channel = self.channel;
+
+        // Don't run our Drop implementation, closing does everything it would do.
+        mem::forget(self);
+
+        unsafe { channel.close(|| release(channel)) }
+    }
+
+    /// Blocks the current thread until the message is sent, or the [`Sender`] is dropped.
+    #[cfg(feature = "std")]
+    pub fn recv(self) -> Result<T, RecvError> {
+        let channel = self.channel;
+
+        // Don't run our Drop implementation if we are receiving consuming ourselves.
+        mem::forget(self);
+
+        // The sender has either sent the message or been dropped. We are the last endpoint.
+        unsafe { channel.recv(channel.wait(None), || release(channel)) }
+    }
+
+    /// Like [`Receiver::recv`], but busy-waits for the sender as described by `strategy` before
+    /// blocking the thread.
+    #[cfg(feature = "std")]
+    pub fn recv_with_strategy(self, strategy: RecvStrategy) -> Result<T, RecvError> {
+        let channel = self.channel;
+
+        // Don't run our Drop implementation if we are receiving consuming ourselves.
+        mem::forget(self);
+
+        unsafe { channel.recv(channel.busy_wait(strategy), || release(channel)) }
+    }
+
+    /// Like [`Receiver::recv`], but does not consume the receiver.
+    #[cfg(feature = "std")]
+    pub fn recv_ref(&self) -> Result<T, RecvError> {
+        unsafe { self.channel.recv_ref(self.channel.wait(None)) }
+            .map_err(RecvTimeoutError::into_recv_error)
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than `timeout`.
+    #[cfg(feature = "std")]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        unsafe { self.channel.recv_ref(self.channel.wait(deadline)) }
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than until `deadline`.
+    #[cfg(feature = "std")]
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        unsafe { self.channel.recv_ref(self.channel.wait(Some(deadline))) }
+    }
+
+    /// Like [`Receiver::recv`], but blocks through `parker`. See the [`park`](crate::park)
+    /// module.
+    #[cfg(feature = "park")]
+    pub fn recv_with<P: Park>(self, parker: &P) -> Result<T, RecvError> {
+        let channel = self.channel;
+
+        // Don't run our Drop implementation if we are receiving consuming ourselves.
+        mem::forget(self);
+
+        unsafe { channel.recv(channel.wait_with(parker), || release(channel)) }
+    }
+
+    /// Like [`Receiver::recv_deadline`], but blocks through `parker` and measures time with
+    /// `clock`. See the [`park`](crate::park) module.
+    #[cfg(feature = "park")]
+    pub fn recv_deadline_with<P: Park, C: Clock>(
+        &self,
+        parker: &P,
+        clock: &C,
+        deadline: C::Instant,
+    ) -> Result<T, RecvTimeoutError> {
+        unsafe {
+            let state = self.channel.wait_deadline_with(parker, clock, &deadline);
+            self.channel.recv_ref(state)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> core::future::Future for Receiver<'_, T> {
+    type Output = Result<T, crate::RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        unsafe { self.channel.poll_recv(cx) }
+    }
+}
+
+impl<T> Drop for Receiver<'_, T> {
+    fn drop(&mut self) {
+        // If the sender already went away, we are the last endpoint.
+        let channel = self.channel;
+        unsafe { channel.drop_receiver(|| release(channel)) };
+    }
+}
+
+impl<T> fmt::Debug for Receiver<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A future that completes when the [`Receiver`] of a channel has been dropped. Created by
+/// [`Sender::closed`].
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct Closed<'s, 'a, T> {
+    sender: &'s mut Sender<'a, T>,
+}
+
+#[cfg(feature = "async")]
+impl<T> core::future::Future for Closed<'_, '_, T> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        self.sender.poll_closed(cx)
+    }
+}
+
+/// An error returned when trying to send on a closed [`StaticChannel`]. Returned from
+/// [`Sender::send`] if the corresponding [`Receiver`] has already been dropped.
+///
+/// The message that could not be sent can be retrieved again with [`SendError::into_inner`].
+/// The channel can't be used again until this error has been dropped or consumed.
+pub struct SendError<'a, T> {
+    channel: &'a Channel<T>,
+}
+
+unsafe impl<T: Send> Send for SendError<'_, T> {}
+unsafe impl<T: Sync> Sync for SendError<'_, T> {}
+
+impl<T> SendError<'_, T> {
+    /// Consumes the error and returns the message that failed to be sent.
+    pub fn into_inner(self) -> T {
+        let channel = self.channel;
+
+        // Don't run destructor if we consumed ourselves. Releasing happens here.
+        mem::forget(self);
+
+        // SAFETY: The message is in the channel, and we are the only ones with access to it.
+        let message = unsafe { channel.take_message() };
+        release(channel);
+        message
+    }
+
+    /// Get a reference to the message that failed to be sent.
+    pub fn as_inner(&self) -> &T {
+        // SAFETY: The message is in the channel until we are dropped or consumed.
+        unsafe { self.channel.message().assume_init_ref() }
+    }
+}
+
+impl<T> Drop for SendError<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: The message is in the channel, and we are the only ones with access to it.
+        unsafe { self.channel.drop_message() };
+        release(self.channel);
+    }
+}
+
+impl<T> fmt::Display for SendError<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "sending on a closed channel".fmt(f)
+    }
+}
+
+impl<T> fmt::Debug for SendError<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SendError<{}>(_)", stringify!(T))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> std::error::Error for SendError<'_, T> {}
@@ -0,0 +1,52 @@
+/// How a blocking receive waits for the sender before blocking the thread.
+///
+/// Blocking the thread and waking it up again takes a few microseconds, which dominates the
+/// latency of a receive if the sender acts shortly after the receiver started waiting. A
+/// strategy can make the receiver busy-wait for the sender first. It first spins, checking the
+/// channel between each spin loop hint, and then yields the thread to the OS scheduler a number
+/// of times. Only if the sender has not acted by then does the thread block.
+///
+/// The busy-waiting never writes to the channel. A sender acting during it does not have to wake
+/// up the receiver.
+///
+/// Busy-waiting burns CPU time, and hurts more than it helps if the sender is not running on
+/// another core. Measure before using it.
+///
+/// # Examples
+///
+/// ```rust
+/// # use oneshot::RecvStrategy;
+/// # use std::thread;
+/// let (sender, receiver) = oneshot::channel();
+/// thread::spawn(move || sender.send(5u32));
+///
+/// let strategy = RecvStrategy::new().spin(100).yield_now(10);
+/// assert_eq!(receiver.recv_with_strategy(strategy), Ok(5));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecvStrategy {
+    pub(crate) spins: u32,
+    pub(crate) yields: u32,
+}
+
+impl RecvStrategy {
+    /// A strategy blocking the thread right away, like [`Receiver::recv`](crate::Receiver::recv).
+    pub const fn new() -> Self {
+        Self {
+            spins: 0,
+            yields: 0,
+        }
+    }
+
+    /// Checks the channel up to `spins` times, with a spin loop hint in between, before moving
+    /// on to the next phase.
+    pub const fn spin(self, spins: u32) -> Self {
+        Self { spins, ..self }
+    }
+
+    /// Checks the channel up to `yields` times after the spin phase, yielding the thread in
+    /// between, before blocking the thread.
+    pub const fn yield_now(self, yields: u32) -> Self {
+        Self { yields, ..self }
+    }
+}
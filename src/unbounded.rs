@@ -0,0 +1,451 @@
+//! An unbounded mpmc channel, where sending never waits for room.
+//!
+//! Like the send of the oneshot channel, sending is not async and never waits for a receiver, so
+//! it can be called from anywhere, threads and async tasks alike. It is lock-free as long as no
+//! receiver is waiting for a message. See the implementation notes below. Messages are queued until a
+//! receiver takes them. Both [`Sender`] and [`Receiver`] can be cloned. Every message is taken by
+//! exactly one receiver, in the order the messages were sent by any single sender.
+//!
+//! Receiving exists in a blocking version, with and without a timeout, and in an async version.
+//! With the `stream` feature, [`Receiver`] also implements `Stream` from the `futures-core`
+//! crate.
+//!
+//! ```rust
+//! use std::thread;
+//!
+//! let (sender, receiver) = oneshot::unbounded::channel();
+//!
+//! for i in 0..3 {
+//!     let sender = sender.clone();
+//!     thread::spawn(move || sender.send(i).unwrap());
+//! }
+//! drop(sender);
+//!
+//! // Once all senders are gone and the channel is empty, `recv` returns an error.
+//! let mut received: Vec<u32> = std::iter::from_fn(|| receiver.recv().ok()).collect();
+//! received.sort();
+//! assert_eq!(received, [0, 1, 2]);
+//! ```
+//!
+//! # Implementation
+//!
+//! Messages are stored in a linked list of heap allocated nodes. A sender swaps its node in
+//! as the new head of the list, and then links the previous head to it. No sender ever waits for
+//! another one. The receivers take messages from the other end of the list, serialized by a
+//! mutex also protecting the queue of waiting receivers.
+//!
+//! A sender only takes that lock if a receiver is waiting for a message, in order to wake it up.
+//! The send is then not lock-free. It can block while another receiver or sender holds the lock,
+//! which is only ever held for short moments. Taking the waker out of the queue without
+//! the lock would need a lock-free queue of waiters, which is not worth it for this case. The
+//! oneshot [`Sender::send`](crate::Sender::send) similarly has to unpark a blocked receiver.
+
+pub use super::recv_errors::{RecvError, RecvTimeoutError, TryRecvError};
+pub use super::send_errors::SendError;
+use super::waiters::{self, Token, Waiters};
+use super::ReceiverWaker;
+use core::fmt;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+use core::ptr;
+#[cfg(feature = "async")]
+use core::task;
+use core::task::Poll;
+use std::time::{Duration, Instant};
+
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{fence, AtomicPtr, AtomicUsize, Ordering::*},
+    Arc, Mutex, MutexGuard,
+};
+#[cfg(not(loom))]
+use std::sync::{
+    atomic::{fence, AtomicPtr, AtomicUsize, Ordering::*},
+    Arc, Mutex, MutexGuard,
+};
+
+/// Creates a new unbounded channel, returning the first sender and receiver of it.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let stub = Node::new(None);
+    let shared = Arc::new(Shared {
+        head: AtomicPtr::new(stub),
+        receiving: Mutex::new(Receiving {
+            tail: stub,
+            waiters: Waiters::default(),
+        }),
+        waiting: AtomicUsize::new(0),
+        sender_count: AtomicUsize::new(1),
+        receiver_count: AtomicUsize::new(1),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver {
+            shared,
+            #[cfg(feature = "stream")]
+            token: None,
+        },
+    )
+}
+
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    message: Option<T>,
+}
+
+impl<T> Node<T> {
+    fn new(message: Option<T>) -> *mut Self {
+        Box::into_raw(Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            message,
+        }))
+    }
+}
+
+struct Shared<T> {
+    /// The node pushed last. Senders swap their new node in here.
+    head: AtomicPtr<Node<T>>,
+    receiving: Mutex<Receiving<T>>,
+    /// How many receivers are registered in `receiving.waiters`. Lets senders find out if they
+    /// have to wake up a receiver without taking the lock.
+    waiting: AtomicUsize,
+    sender_count: AtomicUsize,
+    receiver_count: AtomicUsize,
+}
+
+// SAFETY: Messages are moved from senders to receivers, never shared. The nodes are owned by
+// the channel, and only freed by the receivers while holding the lock.
+unsafe impl<T: Send> Send for Shared<T> {}
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+/// The receiving end of the list, only touched with the lock held.
+struct Receiving<T> {
+    /// The node before the oldest message. Its own message has already been taken.
+    tail: *mut Node<T>,
+    /// Receivers waiting for a message.
+    waiters: Waiters,
+}
+
+impl<T> Receiving<T> {
+    /// Takes the oldest message out of the list. Returns `None` if the list is empty, or if the
+    /// sender of the oldest message has swapped in its node but not linked it yet. That sender
+    /// then checks for waiting receivers after linking its node.
+    fn pop(&mut self) -> Option<T> {
+        // SAFETY: The tail node is only freed here, with the lock held. Acquire makes the
+        // message in the next node visible, it was written before the Release store linking it.
+        unsafe {
+            let next = (*self.tail).next.load(Acquire);
+            if next.is_null() {
+                return None;
+            }
+            drop(Box::from_raw(self.tail));
+            self.tail = next;
+            (*next).message.take()
+        }
+    }
+
+    #[cfg(feature = "async")]
+    fn has_message(&self) -> bool {
+        // SAFETY: See `pop`.
+        !unsafe { (*self.tail).next.load(Acquire) }.is_null()
+    }
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Receiving<T>> {
+        waiters::lock(&self.receiving)
+    }
+
+    /// Updates `waiting` after the waiters changed. Must be called with the lock held.
+    fn update_waiting(&self, receiving: &Receiving<T>) {
+        self.waiting.store(receiving.waiters.len(), Relaxed);
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let mut node = self.lock().tail;
+        while !node.is_null() {
+            // SAFETY: We are the last owner of the list. Every node was allocated by `Node::new`.
+            let boxed = unsafe { Box::from_raw(node) };
+            node = boxed.next.load(Relaxed);
+        }
+    }
+}
+
+/// The sending half of an unbounded channel. Can be cloned to send from many places.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of an unbounded channel. Can be cloned to receive in many places.
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    /// The place of this receiver in the waiters, when waiting as a `Stream`.
+    #[cfg(feature = "stream")]
+    token: Option<Token>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `message` on the channel. Never waits for room or for a receiver. It is lock-free
+    /// unless a receiver is waiting for a message. It then briefly locks the receiving side of
+    /// the channel to wake one of them up.
+    ///
+    /// Returns an error containing the message if all receivers have been dropped. If the last
+    /// receiver is dropped while this method runs, the message can end up in the channel
+    /// without ever being received. It is then dropped when the last sender is gone.
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        if self.shared.receiver_count.load(Acquire) == 0 {
+            return Err(SendError(message));
+        }
+
+        let node = Node::new(Some(message));
+        // Acquire makes the previous node's initialization visible before we write to it, and
+        // Release does the same for our node to the next sender.
+        let previous = self.shared.head.swap(node, AcqRel);
+        // SAFETY: A node is only freed once the receivers have moved past it, which they can't
+        // before its `next` pointer has been set here. Release publishes the message.
+        unsafe { (*previous).next.store(node, Release) };
+
+        // Pairs with the fence in `poll_message`. Either this load sees the receiver waiting, or
+        // the receiver sees the message after registering.
+        fence(SeqCst);
+        if self.shared.waiting.load(Relaxed) > 0 {
+            let mut receiving = self.shared.lock();
+            let receiver = receiving.waiters.notify_one();
+            self.shared.update_waiting(&receiving);
+            drop(receiving);
+            if let Some(receiver) = receiver {
+                receiver.unpark();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.shared.sender_count.fetch_add(1, Relaxed);
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        // Release makes all messages sent by this sender visible to a receiver finding the
+        // count at zero. Acquire does the same for the messages of the other senders.
+        if self.shared.sender_count.fetch_sub(1, AcqRel) == 1 {
+            // Waiting receivers register with the lock held, and check the count after taking
+            // the lock. So they either see the count at zero, or are woken up here.
+            let mut receiving = self.shared.lock();
+            let receivers = receiving.waiters.notify_all();
+            self.shared.update_waiting(&receiving);
+            drop(receiving);
+            waiters::wake_all(receivers);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Takes the oldest message out of the channel without blocking. Returns:
+    ///  * `Ok(message)` if there was a message in the channel.
+    ///  * `Err(Empty)` if the channel is empty, but senders are still alive.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.poll_message(&mut None, None) {
+            Poll::Ready(Ok(message)) => Ok(message),
+            Poll::Ready(Err(RecvTimeoutError::Timeout)) => Err(TryRecvError::Empty),
+            Poll::Ready(Err(RecvTimeoutError::Disconnected)) => Err(TryRecvError::Disconnected),
+            Poll::Pending => unreachable!("recv polled without a waker must complete"),
+        }
+    }
+
+    /// Takes the oldest message out of the channel, blocking the thread while the channel is
+    /// empty. Returns an error if the channel is empty and all senders have been dropped.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.recv_until(None).map_err(|_| RecvError)
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than `timeout`. Returns:
+    ///  * `Ok(message)` if there was a message in the channel before the timeout was reached.
+    ///  * `Err(Timeout)` if the channel stayed empty until the timeout was reached.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    ///
+    /// If the supplied `timeout` is so large that Rust's `Instant` type can't represent this point
+    /// in the future this falls back to an indefinitely blocking receive operation.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Instant::now().checked_add(timeout))
+    }
+
+    /// Like [`Receiver::recv`], but will not block longer than until `deadline`. Returns:
+    ///  * `Ok(message)` if there was a message in the channel before the deadline was reached.
+    ///  * `Err(Timeout)` if the channel stayed empty until the deadline was reached.
+    ///  * `Err(Disconnected)` if the channel is empty and all senders have been dropped.
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        self.recv_until(Some(deadline))
+    }
+
+    /// Takes the oldest message out of the channel, waiting while the channel is empty. The
+    /// async version of [`Receiver::recv`].
+    ///
+    /// Dropping the returned future before it completes never loses a message. Messages are
+    /// only taken out of the channel by the poll that completes the future.
+    #[cfg(feature = "async")]
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            receiver: self,
+            token: None,
+        }
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        let mut token = None;
+        waiters::block(deadline, |waker| self.poll_message(&mut token, waker))
+    }
+
+    /// Takes the oldest message out of the list, if there is one. Registers `waker` if not.
+    /// Without a waker, gives up waiting. Once this returns `Ready`, the receiver is not
+    /// waiting anymore.
+    fn poll_message(
+        &self,
+        token: &mut Option<Token>,
+        waker: Option<ReceiverWaker>,
+    ) -> Poll<Result<T, RecvTimeoutError>> {
+        let shared = &*self.shared;
+        let mut receiving = shared.lock();
+        let result = match receiving.pop() {
+            Some(message) => Poll::Ready(Ok(message)),
+            // Acquire pairs with the Release in `Sender::drop`. Any message sent before the
+            // last sender went away is visible when popping again.
+            None if shared.sender_count.load(Acquire) == 0 => {
+                Poll::Ready(receiving.pop().ok_or(RecvTimeoutError::Disconnected))
+            }
+            None => match waker {
+                Some(waker) => {
+                    receiving.waiters.register(token, waker);
+                    shared.update_waiting(&receiving);
+                    // Pairs with the fence in `Sender::send`.
+                    fence(SeqCst);
+                    match receiving.pop() {
+                        Some(message) => Poll::Ready(Ok(message)),
+                        None => Poll::Pending,
+                    }
+                }
+                None => Poll::Ready(Err(RecvTimeoutError::Timeout)),
+            },
+        };
+        if result.is_ready() {
+            if let Some(token) = token.take() {
+                receiving.waiters.take(token);
+                shared.update_waiting(&receiving);
+            }
+        }
+        result
+    }
+
+    /// Removes a waker registered under `token`, handing on a notification it might have got.
+    #[cfg(feature = "async")]
+    fn cancel(&self, token: Option<Token>) {
+        if token.is_some() {
+            let mut receiving = self.shared.lock();
+            let ready = receiving.has_message();
+            let receiver = receiving.waiters.cancel(token, ready);
+            self.shared.update_waiting(&receiving);
+            drop(receiving);
+            if let Some(receiver) = receiver {
+                receiver.unpark();
+            }
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.shared.receiver_count.fetch_add(1, Relaxed);
+        Self {
+            shared: self.shared.clone(),
+            #[cfg(feature = "stream")]
+            token: None,
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "stream")]
+        self.cancel(self.token);
+
+        if self.shared.receiver_count.fetch_sub(1, AcqRel) == 1 {
+            // Nobody can receive the remaining messages anymore. Drop them outside the lock.
+            let mut receiving = self.shared.lock();
+            let messages: Vec<T> = core::iter::from_fn(|| receiving.pop()).collect();
+            drop(receiving);
+            drop(messages);
+        }
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<T> futures_core::Stream for Receiver<T> {
+    type Item = T;
+
+    /// Only the waker of the latest call is woken up.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        let mut token = this.token.take();
+        let result = this.poll_message(&mut token, Some(ReceiverWaker::task_waker(cx)));
+        this.token = token;
+        result.map(Result::ok)
+    }
+}
+
+/// A future receiving a message from an unbounded channel. Created by
+/// [`Receiver::recv_async`].
+#[cfg(feature = "async")]
+pub struct RecvFuture<'a, T> {
+    receiver: &'a Receiver<T>,
+    token: Option<Token>,
+}
+
+#[cfg(feature = "async")]
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.receiver
+            .poll_message(&mut this.token, Some(ReceiverWaker::task_waker(cx)))
+            .map_err(|_| RecvError)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        self.receiver.cancel(self.token);
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> fmt::Debug for RecvFuture<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecvFuture").finish_non_exhaustive()
+    }
+}
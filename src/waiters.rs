@@ -0,0 +1,140 @@
+//! Queues of blocked threads and tasks, shared by the channels carrying more than one message.
+//!
+//! Those channels keep their state behind a mutex. An operation that can't complete yet
+//! registers a waker in one of the channel's `Waiters` queues while holding the lock, and is
+//! woken up once another operation changed the state in a way that might let it complete. It
+//! then locks the channel and tries again. The wakers are the same `ReceiverWaker` the oneshot
+//! channel stores, so blocked threads and async tasks wait in the same queue.
+//!
+//! Wakers taken out of a queue should be woken up after the lock has been released, so the
+//! woken operation does not immediately block on it.
+
+use super::{thread, ReceiverWaker};
+use alloc::collections::VecDeque;
+use core::mem;
+use core::task::Poll;
+use std::sync::PoisonError;
+use std::time::Instant;
+
+#[cfg(loom)]
+use loom::sync::{Mutex, MutexGuard};
+#[cfg(not(loom))]
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks the state of a channel, ignoring poisoning.
+///
+/// The only user code running while a channel is locked are destructors of wakers, and `clone`
+/// of the message in the channels handing out clones. Neither runs halfway through a change of
+/// the channel state, so a panic in them leaves the channel consistent, and the other endpoints
+/// can keep using it.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Identifies one waiting operation in a `Waiters` queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token(u64);
+
+#[derive(Default)]
+pub struct Waiters {
+    queue: VecDeque<(Token, ReceiverWaker)>,
+    next_token: u64,
+}
+
+impl Waiters {
+    /// Returns a token no other operation waiting in this queue has.
+    pub fn new_token(&mut self) -> Token {
+        let token = Token(self.next_token);
+        self.next_token += 1;
+        token
+    }
+
+    /// Returns how many operations are waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Registers `waker` for the operation identified by `token`, giving the operation a new
+    /// token if it has none yet. If the operation is already waiting, its waker is replaced and
+    /// it keeps its place in the queue.
+    pub fn register(&mut self, token: &mut Option<Token>, waker: ReceiverWaker) {
+        let token = match *token {
+            Some(token) => token,
+            None => *token.insert(self.new_token()),
+        };
+        match self.queue.iter_mut().find(|(waiting, _)| *waiting == token) {
+            Some((_, registered)) => *registered = waker,
+            None => self.queue.push_back((token, waker)),
+        }
+    }
+
+    /// Removes the operation identified by `token` from the queue, and returns its waker.
+    /// Returns `None` if it is not waiting.
+    pub fn take(&mut self, token: Token) -> Option<ReceiverWaker> {
+        let index = self
+            .queue
+            .iter()
+            .position(|(waiting, _)| *waiting == token)?;
+        self.queue.remove(index).map(|(_, waker)| waker)
+    }
+
+    /// Removes the operation that has waited the longest from the queue, and returns its waker.
+    pub fn notify_one(&mut self) -> Option<ReceiverWaker> {
+        self.queue.pop_front().map(|(_, waker)| waker)
+    }
+
+    /// Empties the queue and returns all wakers in it.
+    pub fn notify_all(&mut self) -> impl Iterator<Item = ReceiverWaker> {
+        mem::take(&mut self.queue)
+            .into_iter()
+            .map(|(_, waker)| waker)
+    }
+
+    /// Removes an operation that gives up waiting from the queue. An operation that is not in
+    /// the queue anymore, while still having a token, has been notified. If it gives up without
+    /// acting on the notification, and the channel is still `ready` for the kind of operation
+    /// waiting in this queue, the notification is handed on to the next operation in line. Its
+    /// waker is returned.
+    #[cfg(feature = "async")]
+    pub fn cancel(&mut self, token: Option<Token>, ready: bool) -> Option<ReceiverWaker> {
+        let token = token?;
+        match self.take(token) {
+            Some(_) => None,
+            None if ready => self.notify_one(),
+            None => None,
+        }
+    }
+}
+
+/// Wakes up all `wakers`. Call after releasing the channel lock.
+pub fn wake_all(wakers: impl IntoIterator<Item = ReceiverWaker>) {
+    for waker in wakers {
+        waker.unpark();
+    }
+}
+
+/// Runs an operation blocking the current thread. `poll` tries to complete the operation, and
+/// registers the given waker if it can't. Once `deadline` has passed, `poll` is called one last
+/// time without a waker. It must then complete the operation or give up, and return `Ready`.
+pub fn block<R>(
+    deadline: Option<Instant>,
+    mut poll: impl FnMut(Option<ReceiverWaker>) -> Poll<R>,
+) -> R {
+    let current = thread::current();
+    loop {
+        if let Poll::Ready(result) = poll(Some(ReceiverWaker::thread(current.clone()))) {
+            return result;
+        }
+        match deadline {
+            None => thread::park(),
+            Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                Some(timeout) if !timeout.is_zero() => thread::park_timeout(timeout),
+                _ => break,
+            },
+        }
+    }
+    match poll(None) {
+        Poll::Ready(result) => result,
+        Poll::Pending => unreachable!("operations polled without a waker must complete"),
+    }
+}
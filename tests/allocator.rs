@@ -0,0 +1,142 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+#![cfg(feature = "alloc")]
+
+use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+use core::ptr::NonNull;
+use oneshot::TryRecvError;
+
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{AtomicUsize, Ordering::SeqCst},
+    Arc,
+};
+#[cfg(not(loom))]
+use std::sync::{
+    atomic::{AtomicUsize, Ordering::SeqCst},
+    Arc,
+};
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+/// An allocator keeping track of how many of its allocations are currently alive.
+#[derive(Clone)]
+struct CountingAlloc(Arc<AtomicUsize>);
+
+impl CountingAlloc {
+    fn new() -> Self {
+        Self(Arc::new(AtomicUsize::new(0)))
+    }
+
+    fn live(&self) -> usize {
+        self.0.load(SeqCst)
+    }
+}
+
+unsafe impl Allocator for CountingAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = Global.allocate(layout)?;
+        self.0.fetch_add(1, SeqCst);
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.fetch_sub(1, SeqCst);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+#[test]
+fn send_and_try_recv() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+        let (sender, receiver) = oneshot::channel_in(alloc.clone());
+        assert_eq!(alloc.live(), 1);
+        assert!(sender.send(19i128).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(19i128));
+        assert_eq!(alloc.live(), 1);
+        drop(receiver);
+        assert_eq!(alloc.live(), 0);
+    })
+}
+
+#[test]
+fn drop_receiver_with_message() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+        let (message, counter) = DropCounter::new(());
+        let (sender, receiver) = oneshot::channel_in(alloc.clone());
+        assert!(sender.send(message).is_ok());
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+        assert_eq!(alloc.live(), 0);
+    })
+}
+
+#[test]
+fn send_error() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+
+        let (sender, receiver) = oneshot::channel_in(alloc.clone());
+        drop(receiver);
+        let error = sender.send(5u8).unwrap_err();
+        assert_eq!(alloc.live(), 1);
+        assert_eq!(error.into_inner(), 5);
+        assert_eq!(alloc.live(), 0);
+
+        let (message, counter) = DropCounter::new(());
+        let (sender, receiver) = oneshot::channel_in(alloc.clone());
+        drop(receiver);
+        let error = sender.send(message).unwrap_err();
+        drop(error);
+        assert_eq!(counter.count(), 1);
+        assert_eq!(alloc.live(), 0);
+    })
+}
+
+#[test]
+fn drop_sender() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+        let (sender, receiver) = oneshot::channel_in::<u8, _>(alloc.clone());
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        drop(receiver);
+        assert_eq!(alloc.live(), 0);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn send_and_recv_across_threads() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+        let (sender, receiver) = oneshot::channel_in(alloc.clone());
+        let t = thread::spawn(move || sender.send(9u32));
+        assert_eq!(receiver.recv(), Ok(9));
+        t.join().unwrap().unwrap();
+        assert_eq!(alloc.live(), 0);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn drop_both_endpoints_concurrently() {
+    maybe_loom_model(|| {
+        let alloc = CountingAlloc::new();
+        let (sender, receiver) = oneshot::channel_in::<u8, _>(alloc.clone());
+        let t = thread::spawn(move || drop(sender));
+        drop(receiver);
+        t.join().unwrap();
+        assert_eq!(alloc.live(), 0);
+    })
+}
@@ -1,5 +1,10 @@
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+#![cfg(feature = "alloc")]
+
+use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
 use oneshot::{Receiver, Sender};
 use std::mem;
+use std::ptr::NonNull;
 
 /// Just sanity check that both channel endpoints stay the size of a single pointer.
 #[test]
@@ -18,6 +23,26 @@ fn channel_endpoints_single_pointer() {
     // These tests would fail before switching to `NonNull`
     assert_eq!(mem::size_of::<Option<Sender<[u8; 1024]>>>(), EXPECTED);
     assert_eq!(mem::size_of::<Option<Receiver<[u8; 1024]>>>(), EXPECTED);
+
+    // The allocator is stored in the channel allocation, not in the endpoints
+    assert_eq!(mem::size_of::<Sender<u8, BigAlloc>>(), EXPECTED);
+    assert_eq!(mem::size_of::<Receiver<u8, BigAlloc>>(), EXPECTED);
+    assert_eq!(mem::size_of::<oneshot::SendError<u8, BigAlloc>>(), EXPECTED);
+}
+
+/// An allocator that is much larger than a pointer.
+struct BigAlloc {
+    _padding: [u8; 64],
+}
+
+unsafe impl Allocator for BigAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        Global.deallocate(ptr, layout)
+    }
 }
 
 /// Check that the `SendError` stays small. Useful to automatically detect if it is refactored
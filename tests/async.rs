@@ -1,4 +1,4 @@
-#![cfg(all(feature = "async", not(loom)))]
+#![cfg(all(feature = "alloc", feature = "async", not(loom)))]
 
 use core::mem;
 use core::time::Duration;
@@ -88,6 +88,30 @@ async fn await_before_send_then_drop_sender_async_std() {
     t.await;
 }
 
+#[cfg(feature = "std")]
+#[tokio::test]
+async fn await_before_send_then_panic_in_sender() {
+    let (sender, receiver) = oneshot::channel::<u128>();
+    let t = tokio::spawn(async {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        helpers::drop_while_panicking(sender);
+    });
+    assert_eq!(receiver.await, Err(oneshot::RecvError::SenderPanicked));
+    t.await.unwrap();
+}
+
+#[tokio::test]
+async fn ready_before_send_tokio() {
+    let (sender, mut receiver) = oneshot::channel();
+    let t = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sender.send(19u128).unwrap();
+    });
+    receiver.ready().await;
+    assert_eq!(receiver.try_recv(), Ok(19));
+    t.await.unwrap();
+}
+
 // Tests that the Receiver handles being used synchronously even after being polled
 #[tokio::test]
 async fn poll_future_and_then_try_recv() {
@@ -126,3 +150,38 @@ async fn poll_receiver_then_drop_it() {
     // Make sure the receiver has been dropped by the runtime.
     assert!(sender.send(()).is_err());
 }
+
+#[tokio::test]
+async fn closed_before_drop_receiver_tokio() {
+    let (mut sender, receiver) = oneshot::channel::<u128>();
+    let t = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        mem::drop(receiver);
+    });
+    sender.closed().await;
+    assert!(sender.is_closed());
+    t.await.unwrap();
+}
+
+#[async_std::test]
+async fn closed_before_drop_receiver_async_std() {
+    let (mut sender, receiver) = oneshot::channel::<u128>();
+    let t = async_std::task::spawn(async move {
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        mem::drop(receiver);
+    });
+    sender.closed().await;
+    assert!(sender.is_closed());
+    t.await;
+}
+
+#[tokio::test]
+async fn poll_closed_then_send() {
+    let (mut sender, receiver) = oneshot::channel::<u128>();
+    // This will poll the closed future and then give up after 10 ms.
+    tokio::time::timeout(Duration::from_millis(10), sender.closed())
+        .await
+        .unwrap_err();
+    sender.send(5).unwrap();
+    assert_eq!(receiver.await, Ok(5));
+}
@@ -0,0 +1,255 @@
+#![cfg(feature = "std")]
+
+use core::time::Duration;
+use oneshot::bounded::{
+    self, RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError,
+};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn send_and_recv_in_order() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(2);
+        sender.send(1u32).unwrap();
+        assert_eq!(sender.try_send(2), Ok(()));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    })
+}
+
+#[test]
+fn try_send_on_full_channel() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        sender.try_send(1u32).unwrap();
+        assert!(matches!(sender.try_send(2), Err(TrySendError::Full(2))));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(sender.try_send(3), Ok(()));
+    })
+}
+
+#[test]
+fn send_timeout_on_full_channel() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        sender.send(1u32).unwrap();
+        assert!(matches!(
+            sender.send_timeout(2, Duration::from_millis(1)),
+            Err(SendTimeoutError::Timeout(2))
+        ));
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    })
+}
+
+#[test]
+fn recv_timeout_on_empty_channel() {
+    maybe_loom_model(|| {
+        let (_sender, receiver) = bounded::channel::<u32>(1);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    })
+}
+
+#[test]
+fn send_blocks_while_full() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        let t = thread::spawn(move || {
+            sender.send(1u32).unwrap();
+            sender.send(2).unwrap();
+        });
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn recv_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        let t = thread::spawn(move || receiver.recv());
+        sender.send(19u128).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+    })
+}
+
+#[test]
+fn messages_remain_after_senders_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(2);
+        sender.send(1u32).unwrap();
+        drop(sender);
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    })
+}
+
+#[test]
+fn send_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        drop(receiver);
+        assert_eq!(sender.send(19u128), Err(SendError(19)));
+        assert!(matches!(
+            sender.try_send(19),
+            Err(TrySendError::Disconnected(19))
+        ));
+    })
+}
+
+#[test]
+fn drop_receiver_while_sending() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        let (message1, counter1) = DropCounter::new(1u32);
+        let (message2, counter2) = DropCounter::new(2u32);
+        sender.send(message1).unwrap();
+        let t = thread::spawn(move || drop(receiver));
+        // The channel is full, so this blocks until the receiver is dropped.
+        let error = sender.send(message2).unwrap_err();
+        assert_eq!(*error.into_inner().value(), 2);
+        t.join().unwrap();
+        // The message left in the channel was dropped together with the receiver.
+        assert_eq!(counter1.count(), 1);
+        assert_eq!(counter2.count(), 1);
+    })
+}
+
+#[test]
+fn drop_sender_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel::<u32>(1);
+        let t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn cloned_senders_and_receivers() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = bounded::channel(1);
+        let (sender2, receiver2) = (sender.clone(), receiver.clone());
+        let t1 = thread::spawn(move || sender2.send(1u32).unwrap());
+        let t2 = thread::spawn(move || receiver2.recv().unwrap());
+        sender.send(2).unwrap();
+        let received = receiver.recv().unwrap() + t2.join().unwrap();
+        assert_eq!(received, 3);
+        t1.join().unwrap();
+
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    })
+}
+
+#[test]
+#[should_panic(expected = "zero capacity")]
+fn zero_capacity() {
+    let _ = bounded::channel::<u32>(0);
+}
+
+#[cfg(not(loom))]
+#[test]
+fn many_senders_and_receivers() {
+    let (sender, receiver) = bounded::channel(4);
+    let senders: Vec<_> = (0..4u64)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || (0..1000).for_each(|j| sender.send(i * 1000 + j).unwrap()))
+        })
+        .collect();
+    drop(sender);
+    let receivers: Vec<_> = (0..4)
+        .map(|_| {
+            let receiver = receiver.clone();
+            thread::spawn(move || std::iter::from_fn(|| receiver.recv().ok()).sum::<u64>())
+        })
+        .collect();
+    drop(receiver);
+
+    senders.into_iter().for_each(|t| t.join().unwrap());
+    let sum: u64 = receivers.into_iter().map(|t| t.join().unwrap()).sum();
+    assert_eq!(sum, (0..4000).sum());
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+mod asynchronous {
+    use super::*;
+
+    #[tokio::test]
+    async fn send_async_to_thread() {
+        let (sender, receiver) = bounded::channel(1);
+        let t = std::thread::spawn(move || std::iter::from_fn(|| receiver.recv().ok()).count());
+        for i in 0..100u32 {
+            sender.send_async(i).await.unwrap();
+        }
+        drop(sender);
+        assert_eq!(t.join().unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn recv_async_from_thread() {
+        let (sender, receiver) = bounded::channel(1);
+        let t = std::thread::spawn(move || (0..100u32).for_each(|i| sender.send(i).unwrap()));
+        for i in 0..100 {
+            assert_eq!(receiver.recv_async().await, Ok(i));
+        }
+        assert_eq!(receiver.recv_async().await, Err(RecvError));
+        t.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_async_to_recv_async() {
+        let (sender, receiver) = bounded::channel(1);
+        let send = async {
+            for i in 0..10u32 {
+                sender.send_async(i).await.unwrap();
+            }
+        };
+        let recv = async {
+            for i in 0..10 {
+                assert_eq!(receiver.recv_async().await, Ok(i));
+            }
+        };
+        tokio::join!(send, recv);
+    }
+
+    #[tokio::test]
+    async fn dropping_send_future_drops_message() {
+        let (sender, receiver) = bounded::channel(1);
+        let (message1, _) = DropCounter::new(1u32);
+        let (message2, counter) = DropCounter::new(2u32);
+        sender.send(message1).unwrap();
+        let timeout = tokio::time::timeout(Duration::from_millis(1), sender.send_async(message2));
+        assert!(timeout.await.is_err());
+        assert_eq!(counter.count(), 1);
+        assert_eq!(receiver.try_recv().map(DropCounter::into_value), Ok(1));
+        assert_eq!(receiver.try_recv().err(), Some(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn dropping_recv_future_loses_no_message() {
+        let (sender, receiver) = bounded::channel(1);
+        let timeout = tokio::time::timeout(Duration::from_millis(1), receiver.recv_async());
+        assert!(timeout.await.is_err());
+        let t = std::thread::spawn(move || sender.send(19u128));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+}
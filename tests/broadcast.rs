@@ -0,0 +1,231 @@
+#![cfg(feature = "std")]
+
+use core::time::Duration;
+use oneshot::broadcast::{self, RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn every_receiver_gets_every_message() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver1) = broadcast::channel(2);
+        let mut receiver2 = sender.subscribe();
+        sender.send(1u32).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(receiver1.try_recv(), Ok(1));
+        assert_eq!(receiver1.recv(), Ok(2));
+        assert_eq!(receiver2.recv_timeout(Duration::from_millis(1)), Ok(1));
+        assert_eq!(receiver2.try_recv(), Ok(2));
+        assert_eq!(receiver1.try_recv(), Err(TryRecvError::Empty));
+        assert_eq!(receiver2.try_recv(), Err(TryRecvError::Empty));
+    })
+}
+
+#[test]
+fn subscribe_sees_later_messages_only() {
+    maybe_loom_model(|| {
+        let (sender, _receiver) = broadcast::channel(4);
+        sender.send(1u32).unwrap();
+        let mut receiver = sender.subscribe();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        sender.send(2).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(2));
+    })
+}
+
+#[test]
+fn cloned_receiver_keeps_position() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver) = broadcast::channel(4);
+        sender.send(1u32).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(receiver.recv(), Ok(1));
+        let mut receiver2 = receiver.clone();
+        assert_eq!(receiver2.recv(), Ok(2));
+        assert_eq!(receiver.recv(), Ok(2));
+    })
+}
+
+#[test]
+fn slow_receiver_lags() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver) = broadcast::channel(2);
+        for i in 0..5u32 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Lagged(3)));
+        assert_eq!(receiver.recv(), Ok(3));
+        assert_eq!(receiver.recv(), Ok(4));
+
+        for i in 5..8 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(receiver.recv(), Err(RecvError::Lagged(1)));
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(1)), Ok(6));
+    })
+}
+
+#[test]
+fn recv_timeout_without_messages() {
+    maybe_loom_model(|| {
+        let (_sender, mut receiver) = broadcast::channel::<u32>(1);
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    })
+}
+
+#[test]
+fn recv_before_send() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver) = broadcast::channel(1);
+        let t = thread::spawn(move || receiver.recv());
+        sender.send(19u128).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+    })
+}
+
+#[test]
+fn messages_remain_after_senders_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver) = broadcast::channel(2);
+        let t = thread::spawn(move || sender.send(1u32).unwrap());
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn send_without_receivers() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = broadcast::channel(1);
+        drop(receiver);
+        assert_eq!(sender.send(19u128), Err(SendError(19)));
+
+        // Subscribing again makes sending possible again.
+        let mut receiver = sender.subscribe();
+        sender.send(20).unwrap();
+        assert_eq!(receiver.recv(), Ok(20));
+    })
+}
+
+#[test]
+fn messages_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = broadcast::channel(1);
+        let (message1, counter1) = DropCounter::new(1u32);
+        let (message2, counter2) = DropCounter::new(2u32);
+        // Messages must be `Clone`, the channel only holds the `Arc`s.
+        sender.send(std::sync::Arc::new(message1)).unwrap();
+        sender.send(std::sync::Arc::new(message2)).unwrap();
+        // The first message was overwritten by the second one.
+        assert_eq!(counter1.count(), 1);
+        drop(receiver);
+        // Nobody can receive the second message anymore.
+        assert_eq!(counter2.count(), 1);
+    })
+}
+
+#[test]
+fn drop_sender_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver) = broadcast::channel::<u32>(1);
+        let t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv(), Err(RecvError::Disconnected));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn concurrent_receivers() {
+    maybe_loom_model(|| {
+        let (sender, mut receiver1) = broadcast::channel(2);
+        let mut receiver2 = sender.subscribe();
+        let t = thread::spawn(move || (receiver2.recv(), receiver2.recv()));
+        sender.send(1u32).unwrap();
+        sender.send(2).unwrap();
+        drop(sender);
+        assert_eq!(receiver1.recv(), Ok(1));
+        assert_eq!(receiver1.recv(), Ok(2));
+        assert_eq!(t.join().unwrap(), (Ok(1), Ok(2)));
+    })
+}
+
+#[test]
+#[should_panic(expected = "zero capacity")]
+fn zero_capacity() {
+    let _ = broadcast::channel::<u32>(0);
+}
+
+#[cfg(not(loom))]
+#[test]
+fn many_senders_and_receivers() {
+    let (sender, receiver) = broadcast::channel(4000);
+    let receivers: Vec<_> = (0..4)
+        .map(|_| {
+            let mut receiver = receiver.clone();
+            thread::spawn(move || std::iter::from_fn(|| receiver.recv().ok()).sum::<u64>())
+        })
+        .collect();
+    drop(receiver);
+    let senders: Vec<_> = (0..4u64)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || (0..1000).for_each(|j| sender.send(i * 1000 + j).unwrap()))
+        })
+        .collect();
+    drop(sender);
+
+    senders.into_iter().for_each(|t| t.join().unwrap());
+    for t in receivers {
+        assert_eq!(t.join().unwrap(), (0..4000).sum());
+    }
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+mod asynchronous {
+    use super::*;
+
+    #[tokio::test]
+    async fn thread_and_task_subscribe() {
+        let (sender, mut receiver) = broadcast::channel(100);
+        let mut receiver2 = sender.subscribe();
+        let t = std::thread::spawn(move || std::iter::from_fn(|| receiver2.recv().ok()).count());
+        std::thread::spawn(move || (0..100u32).for_each(|i| sender.send(i).unwrap()));
+        for i in 0..100 {
+            assert_eq!(receiver.recv_async().await, Ok(i));
+        }
+        assert_eq!(receiver.recv_async().await, Err(RecvError::Disconnected));
+        assert_eq!(t.join().unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn recv_async_lags() {
+        let (sender, mut receiver) = broadcast::channel(1);
+        sender.send(1u32).unwrap();
+        sender.send(2).unwrap();
+        assert_eq!(receiver.recv_async().await, Err(RecvError::Lagged(1)));
+        assert_eq!(receiver.recv_async().await, Ok(2));
+    }
+
+    #[tokio::test]
+    async fn dropping_recv_future_loses_no_message() {
+        let (sender, mut receiver) = broadcast::channel(1);
+        let timeout = tokio::time::timeout(Duration::from_millis(1), receiver.recv_async());
+        assert!(timeout.await.is_err());
+        let t = std::thread::spawn(move || sender.send(19u128));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+}
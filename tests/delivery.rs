@@ -0,0 +1,266 @@
+#![cfg(all(feature = "alloc", feature = "std"))]
+
+use core::mem;
+use oneshot::{Dropped, RecvError, TryRecvError};
+use std::time::Duration;
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::{sleep, spawn};
+
+    #[cfg(loom)]
+    pub fn sleep(_timeout: core::time::Duration) {
+        loom::thread::yield_now()
+    }
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn received_before_wait() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(19));
+        assert_eq!(delivery.wait(), Ok(()));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+    })
+}
+
+#[test]
+fn received_with_recv() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(delivery.wait(), Ok(()));
+    })
+}
+
+#[test]
+fn received_then_receiver_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        assert_eq!(receiver.recv_ref(), Ok(19));
+        mem::drop(receiver);
+        assert_eq!(delivery.wait(), Ok(()));
+    })
+}
+
+#[test]
+fn receiver_dropped_with_message() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        let delivery = sender.send_acked(message).unwrap();
+        mem::drop(receiver);
+        assert_eq!(counter.count(), 0);
+
+        let Dropped(message) = delivery.wait().unwrap_err();
+        assert_eq!(counter.count(), 0);
+        mem::drop(message);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[test]
+fn receiver_dropped_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        mem::drop(receiver);
+        let send_error = sender.send_acked(5u8).unwrap_err();
+        assert_eq!(send_error.into_inner(), 5);
+    })
+}
+
+#[test]
+fn delivery_dropped_before_receive() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        mem::drop(sender.send_acked(message).unwrap());
+        assert_eq!(counter.count(), 0);
+
+        let message = receiver.recv().unwrap();
+        assert_eq!(counter.count(), 0);
+        mem::drop(message);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[test]
+fn delivery_and_receiver_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        let delivery = sender.send_acked(message).unwrap();
+        mem::drop(receiver);
+        mem::drop(delivery);
+        assert_eq!(counter.count(), 1);
+    });
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        let delivery = sender.send_acked(message).unwrap();
+        mem::drop(delivery);
+        mem::drop(receiver);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[test]
+fn wait_timeout() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        assert!(!delivery.wait_timeout(Duration::from_millis(1)));
+
+        assert_eq!(receiver.try_recv(), Ok(19));
+        assert!(delivery.wait_timeout(Duration::from_millis(1)));
+        assert_eq!(delivery.wait(), Ok(()));
+    })
+}
+
+#[test]
+fn wait_for_receive_in_other_thread() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            assert_eq!(receiver.recv(), Ok(19));
+        });
+        assert_eq!(delivery.wait(), Ok(()));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn wait_for_drop_in_other_thread() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let delivery = sender.send_acked(19u128).unwrap();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            mem::drop(receiver);
+        });
+        assert_eq!(delivery.wait(), Err(Dropped(19)));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn send_acked_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender.send_acked(19u128).unwrap().wait()
+        });
+        assert_eq!(receiver.recv_ref(), Ok(19));
+        assert_eq!(receiver.recv_ref(), Err(RecvError::SenderDropped));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    })
+}
+
+#[test]
+fn send_acked_while_receiver_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || mem::drop(receiver));
+        match sender.send_acked(19u128) {
+            Ok(delivery) => assert_eq!(delivery.wait(), Err(Dropped(19))),
+            Err(send_error) => assert_eq!(send_error.into_inner(), 19),
+        }
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn send_acked_after_poll_closed_while_receiver_dropped() {
+    maybe_loom_model(|| {
+        let (mut sender, receiver) = oneshot::channel();
+        let (waker, wakeups) = helpers::counting_waker();
+        let mut cx = core::task::Context::from_waker(&waker);
+        assert!(sender.poll_closed(&mut cx).is_pending());
+
+        let t = thread::spawn(move || mem::drop(receiver));
+        match sender.send_acked(19u128) {
+            Ok(delivery) => assert_eq!(delivery.wait(), Err(Dropped(19))),
+            Err(send_error) => assert_eq!(send_error.into_inner(), 19),
+        }
+        t.join().unwrap();
+        assert!(wakeups.load(core::sync::atomic::Ordering::SeqCst) <= 1);
+    })
+}
+
+#[test]
+fn delivery_dropped_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        let delivery = sender.send_acked(message).unwrap();
+        let t = thread::spawn(move || mem::drop(delivery));
+        assert!(receiver.recv().is_ok());
+        t.join().unwrap();
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+#[tokio::test]
+async fn await_delivery() {
+    let (sender, receiver) = oneshot::channel();
+    let delivery = sender.send_acked(19u128).unwrap();
+    let t = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        assert_eq!(receiver.await, Ok(19));
+    });
+    assert_eq!(delivery.await, Ok(()));
+    t.await.unwrap();
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+#[tokio::test]
+async fn await_delivery_receiver_dropped() {
+    let (sender, receiver) = oneshot::channel();
+    let delivery = sender.send_acked(19u128).unwrap();
+    let t = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        mem::drop(receiver);
+    });
+    assert_eq!(delivery.await, Err(Dropped(19)));
+    t.await.unwrap();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn poll_then_wait() {
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{self, Poll};
+
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let mut delivery = sender.send_acked(19u128).unwrap();
+
+        let (waker, wakeups) = helpers::counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+        assert_eq!(Pin::new(&mut delivery).poll(&mut context), Poll::Pending);
+
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            assert_eq!(receiver.recv(), Ok(19));
+        });
+        assert_eq!(delivery.wait(), Ok(()));
+        t.join().unwrap();
+
+        // The task waker was replaced by the blocking wait, so it was never woken.
+        assert_eq!(wakeups.load(core::sync::atomic::Ordering::SeqCst), 0);
+    })
+}
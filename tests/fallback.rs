@@ -0,0 +1,116 @@
+#![cfg(feature = "alloc")]
+
+use core::mem;
+use oneshot::TryRecvError;
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn fallback_on_drop() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let sender = sender.or_on_drop(0u32);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        mem::drop(sender);
+        assert_eq!(receiver.try_recv(), Ok(0));
+    })
+}
+
+#[test]
+fn send_replaces_fallback() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (fallback, counter) = DropCounter::new(0u32);
+        let sender = sender.or_on_drop(fallback);
+        assert!(sender.send(DropCounter::new(1u32).0).is_ok());
+        assert_eq!(counter.count(), 1);
+        assert_eq!(*receiver.try_recv().unwrap().value(), 1);
+    })
+}
+
+#[test]
+fn fallback_with_is_lazy() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let sender = sender.or_on_drop_with(|| -> u32 { panic!("fallback created") });
+        assert!(sender.send(1).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(1));
+
+        let (sender, receiver) = oneshot::channel();
+        mem::drop(sender.or_on_drop_with(|| 2u32));
+        assert_eq!(receiver.try_recv(), Ok(2));
+    })
+}
+
+#[test]
+fn into_sender_drops_fallback() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (fallback, counter) = DropCounter::new(0u32);
+        let sender = sender.or_on_drop(fallback).into_sender();
+        assert_eq!(counter.count(), 1);
+        mem::drop(sender);
+        assert!(matches!(
+            receiver.try_recv(),
+            Err(TryRecvError::SenderDropped)
+        ));
+    })
+}
+
+#[test]
+fn fallback_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (fallback, counter) = DropCounter::new(0u32);
+        let sender = sender.or_on_drop(fallback);
+        mem::drop(receiver);
+        assert!(sender.is_closed());
+        mem::drop(sender);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fallback_on_drop_during_panic() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        helpers::drop_while_panicking(sender.or_on_drop(0u32));
+        assert_eq!(receiver.recv(), Ok(0));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fallback_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let sender = sender.or_on_drop_with(|| 0u32);
+        let t = thread::spawn(move || mem::drop(sender));
+        assert_eq!(receiver.recv(), Ok(0));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fallback_while_dropping_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (fallback, counter) = DropCounter::new(0u32);
+        let sender = sender.or_on_drop(fallback);
+        let t = thread::spawn(move || mem::drop(receiver));
+        mem::drop(sender);
+        t.join().unwrap();
+        assert_eq!(counter.count(), 1);
+    })
+}
@@ -1,4 +1,4 @@
-#![cfg(feature = "async")]
+#![cfg(all(feature = "alloc", feature = "async"))]
 
 use core::{future, mem, pin, task};
 
@@ -7,8 +7,21 @@ pub use loom::sync::{Arc, Mutex};
 #[cfg(not(loom))]
 pub use std::sync::{Arc, Mutex};
 
+#[cfg(feature = "std")]
+use oneshot::{RecvError, RecvTimeoutError};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
 mod helpers;
-use helpers::maybe_loom_model;
+use helpers::{counting_waker, maybe_loom_model};
 
 #[test]
 fn multiple_receiver_polls_keeps_only_latest_waker() {
@@ -63,3 +76,157 @@ fn multiple_receiver_polls_keeps_only_latest_waker() {
         assert_eq!(mock_waker1.lock().unwrap().dropped, 1);
     });
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn poll_then_recv() {
+    maybe_loom_model(|| {
+        let (waker, wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        let poll_result = future::Future::poll(pin::Pin::new(&mut receiver), &mut context);
+        assert_eq!(poll_result, task::Poll::Pending);
+
+        let t = thread::spawn(move || sender.send(5).unwrap());
+        assert_eq!(receiver.recv(), Ok(5));
+        t.join().unwrap();
+        // The task waker is only woken up if the sender managed to take it before the receiver
+        // replaced it with the thread waker.
+        assert!(wakeups.load(core::sync::atomic::Ordering::SeqCst) <= 1);
+    })
+}
+
+#[test]
+fn poll_ready_leaves_message_in_channel() {
+    maybe_loom_model(|| {
+        let (waker, wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        assert_eq!(receiver.poll_ready(&mut context), task::Poll::Pending);
+        sender.send(19).unwrap();
+        assert_eq!(wakeups.load(core::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(receiver.poll_ready(&mut context), task::Poll::Ready(()));
+        assert_eq!(receiver.try_recv(), Ok(19));
+    })
+}
+
+#[test]
+fn polled_receiver_state() {
+    maybe_loom_model(|| {
+        let (waker, _wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        assert_eq!(receiver.poll_ready(&mut context), task::Poll::Pending);
+        let receiving = oneshot::ChannelState::Receiving { sync: false };
+        assert_eq!(sender.state(), receiving);
+        assert_eq!(receiver.state(), receiving);
+        mem::drop(sender);
+        assert!(receiver.is_closed());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn poll_ready_then_wait() {
+    maybe_loom_model(|| {
+        let (waker, _wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        assert_eq!(receiver.poll_ready(&mut context), task::Poll::Pending);
+
+        let t = thread::spawn(move || sender.send(19).unwrap());
+        receiver.wait();
+        assert_eq!(receiver.poll_ready(&mut context), task::Poll::Ready(()));
+        assert_eq!(
+            future::Future::poll(pin::Pin::new(&mut receiver), &mut context),
+            task::Poll::Ready(Ok(19))
+        );
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn poll_then_recv_ref_with_dropped_sender() {
+    maybe_loom_model(|| {
+        let (waker, _wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        let poll_result = future::Future::poll(pin::Pin::new(&mut receiver), &mut context);
+        assert_eq!(poll_result, task::Poll::Pending);
+
+        let t = thread::spawn(move || mem::drop(sender));
+        assert_eq!(receiver.recv_ref(), Err(RecvError::SenderDropped));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn poll_then_recv_timeout_then_poll() {
+    maybe_loom_model(|| {
+        let (waker, wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        let poll_result = future::Future::poll(pin::Pin::new(&mut receiver), &mut context);
+        assert_eq!(poll_result, task::Poll::Pending);
+
+        assert_eq!(
+            receiver.recv_deadline(Instant::now()),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        let poll_result = future::Future::poll(pin::Pin::new(&mut receiver), &mut context);
+        assert_eq!(poll_result, task::Poll::Pending);
+
+        let t = thread::spawn(move || sender.send(5).unwrap());
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)), Ok(5));
+        t.join().unwrap();
+        assert!(wakeups.load(core::sync::atomic::Ordering::SeqCst) <= 1);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn poll_while_sending_then_recv() {
+    maybe_loom_model(|| {
+        let (waker, _wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || sender.send(5).unwrap());
+        match future::Future::poll(pin::Pin::new(&mut receiver), &mut context) {
+            task::Poll::Ready(result) => assert_eq!(result, Ok(5)),
+            task::Poll::Pending => assert_eq!(receiver.recv(), Ok(5)),
+        }
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn poll_then_drop_receiver_while_sending() {
+    maybe_loom_model(|| {
+        let (waker, _wakeups) = counting_waker();
+        let mut context = task::Context::from_waker(&waker);
+
+        let (sender, mut receiver) = oneshot::channel::<u128>();
+        let poll_result = future::Future::poll(pin::Pin::new(&mut receiver), &mut context);
+        assert_eq!(poll_result, task::Poll::Pending);
+
+        #[cfg(loom)]
+        let t = loom::thread::spawn(move || {
+            let _ = sender.send(5);
+        });
+        mem::drop(receiver);
+        #[cfg(loom)]
+        t.join().unwrap();
+        #[cfg(not(loom))]
+        assert!(sender.send(5).is_err());
+    })
+}
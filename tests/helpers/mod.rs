@@ -58,3 +58,46 @@ impl<T> Drop for DropCounter<T> {
         self.drop_count.fetch_add(1, SeqCst);
     }
 }
+
+/// A `Waker` that counts how many times it has been woken. The counter is shared by all clones.
+#[cfg(feature = "async")]
+pub fn counting_waker() -> (core::task::Waker, Arc<AtomicUsize>) {
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    unsafe fn clone(counter: *const ()) -> RawWaker {
+        let counter = Arc::from_raw(counter as *const AtomicUsize);
+        let clone = counter.clone();
+        core::mem::forget(counter);
+        RawWaker::new(Arc::into_raw(clone) as *const (), &VTABLE)
+    }
+
+    unsafe fn wake(counter: *const ()) {
+        wake_by_ref(counter);
+        drop_waker(counter);
+    }
+
+    unsafe fn wake_by_ref(counter: *const ()) {
+        (*(counter as *const AtomicUsize)).fetch_add(1, SeqCst);
+    }
+
+    unsafe fn drop_waker(counter: *const ()) {
+        drop(Arc::from_raw(counter as *const AtomicUsize));
+    }
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop_waker);
+
+    let counter = Arc::new(AtomicUsize::new(0));
+    let raw_waker = RawWaker::new(Arc::into_raw(counter.clone()) as *const (), &VTABLE);
+    (unsafe { Waker::from_raw(raw_waker) }, counter)
+}
+
+/// Drops `value` while the current thread is unwinding from a panic, then stops the unwinding.
+/// Resuming an unwind does not run the panic hook, so nothing is printed.
+#[cfg(feature = "std")]
+pub fn drop_while_panicking<T>(value: T) {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+        let _value = value;
+        std::panic::resume_unwind(alloc::boxed::Box::new(()));
+    }));
+    assert!(result.is_err());
+}
@@ -0,0 +1,189 @@
+#![cfg(feature = "park")]
+
+#[cfg(feature = "alloc")]
+use core::mem;
+use core::time::Duration;
+#[cfg(feature = "alloc")]
+use oneshot::RecvTimeoutError;
+use oneshot::{Clock, Park, RecvError, StaticChannel};
+use std::cell::Cell;
+use std::sync::Arc;
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::{current, park, spawn, yield_now, Thread};
+    #[cfg(not(loom))]
+    pub use std::thread::{current, park, spawn, yield_now, Thread};
+}
+
+mod helpers;
+use helpers::maybe_loom_model;
+#[cfg(feature = "alloc")]
+use helpers::DropCounter;
+
+/// A parker written like a user of the `park` feature would, parking the current thread.
+/// Counts how many times it parked.
+#[derive(Default)]
+struct TestParker {
+    parks: Cell<usize>,
+}
+
+struct TestUnparker(thread::Thread);
+
+impl std::task::Wake for TestUnparker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+impl Park for TestParker {
+    // `Unpark` is only implemented for `Arc` with the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    type Unparker = Arc<TestUnparker>;
+    #[cfg(not(feature = "alloc"))]
+    type Unparker = core::task::Waker;
+
+    #[allow(clippy::useless_conversion)]
+    fn unparker(&self) -> Self::Unparker {
+        Arc::new(TestUnparker(thread::current())).into()
+    }
+
+    fn park(&self) {
+        self.parks.set(self.parks.get() + 1);
+        thread::park()
+    }
+
+    // Timeouts are measured by `TickClock`, so time does not need to pass here.
+    fn park_timeout(&self, _timeout: Duration) {
+        self.parks.set(self.parks.get() + 1);
+        thread::yield_now()
+    }
+}
+
+/// A clock where every look at the time advances it one tick.
+#[derive(Default)]
+struct TickClock {
+    now: Cell<u64>,
+}
+
+impl Clock for TickClock {
+    type Instant = u64;
+
+    fn time_until(&self, deadline: &u64) -> Option<Duration> {
+        let now = self.now.get();
+        self.now.set(now + 1);
+        deadline.checked_sub(now).map(Duration::from_millis)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn send_before_recv_with() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        sender.send(19u128).unwrap();
+        let parker = TestParker::default();
+        assert_eq!(receiver.recv_with(&parker), Ok(19));
+        assert_eq!(parker.parks.get(), 0);
+    })
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recv_with_across_threads() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || sender.send(19u128).unwrap());
+        assert_eq!(receiver.recv_with(&TestParker::default()), Ok(19));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recv_with_dropped_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || mem::drop(sender));
+        assert_eq!(
+            receiver.recv_with(&TestParker::default()),
+            Err(RecvError::SenderDropped)
+        );
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recv_deadline_with_passed() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (parker, clock) = (TestParker::default(), TickClock::default());
+        assert!(matches!(
+            receiver.recv_deadline_with(&parker, &clock, 3),
+            Err(RecvTimeoutError::Timeout)
+        ));
+        // The clock was looked at for each park, and once more to find the deadline passed.
+        assert_eq!(parker.parks.get(), 4);
+        assert_eq!(clock.now.get(), 5);
+
+        // No waker is left behind in the channel after the timeout.
+        let (message, counter) = DropCounter::new(());
+        sender.send(message).unwrap();
+        assert!(receiver.recv_with(&parker).is_ok());
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn recv_deadline_with_across_threads() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || sender.send(19u128).unwrap());
+        let (parker, clock) = (TestParker::default(), TickClock::default());
+        match receiver.recv_deadline_with(&parker, &clock, 1) {
+            Ok(message) => assert_eq!(message, 19),
+            Err(error) => {
+                assert_eq!(error, RecvTimeoutError::Timeout);
+                assert_eq!(receiver.recv_with(&parker), Ok(19));
+            }
+        }
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn static_channel_recv_with() {
+    maybe_loom_model(|| {
+        let channel: &'static StaticChannel<u128> = Box::leak(Box::new(StaticChannel::new()));
+        let (sender, receiver) = channel.channel().unwrap();
+        let t = thread::spawn(move || sender.send(19).unwrap());
+        let (parker, clock) = (TestParker::default(), TickClock::default());
+        assert_eq!(
+            receiver.recv_deadline_with(&parker, &clock, u64::MAX),
+            Ok(19)
+        );
+        assert_eq!(receiver.recv_with(&parker), Err(RecvError::SenderDropped));
+        t.join().unwrap();
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_parker_and_clock() {
+    use oneshot::park::{StdClock, ThreadParker};
+    use std::time::Instant;
+
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || sender.send(19u128).unwrap());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert_eq!(
+            receiver.recv_deadline_with(&ThreadParker::new(), &StdClock, deadline),
+            Ok(19)
+        );
+        t.join().unwrap();
+    })
+}
@@ -0,0 +1,103 @@
+#![cfg(all(target_os = "linux", feature = "eventfd", not(loom)))]
+
+use core::mem;
+use oneshot::{ChannelState, PollableReceiver, TryRecvError};
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::unix::io::AsFd;
+use std::thread;
+use std::time::Duration;
+
+/// Returns true if the eventfd of `receiver` is readable, consuming its counter.
+fn take_readable<T>(receiver: &PollableReceiver<T>) -> bool {
+    let mut file = File::from(receiver.as_fd().try_clone_to_owned().unwrap());
+    let mut counter = [0u8; 8];
+    match file.read(&mut counter) {
+        Ok(8) => true,
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => false,
+        result => panic!("unexpected eventfd read result: {:?}", result),
+    }
+}
+
+#[test]
+fn send_makes_fd_readable() {
+    let (sender, receiver) = oneshot::channel();
+    let receiver = receiver.into_pollable().unwrap();
+    assert!(!take_readable(&receiver));
+    assert_eq!(receiver.state(), ChannelState::Receiving { sync: false });
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+
+    sender.send(19u128).unwrap();
+    assert!(take_readable(&receiver));
+    assert_eq!(receiver.try_recv(), Ok(19));
+}
+
+#[test]
+fn drop_sender_makes_fd_readable() {
+    let (sender, receiver) = oneshot::channel::<u128>();
+    let receiver = receiver.into_pollable().unwrap();
+    mem::drop(sender);
+    assert!(take_readable(&receiver));
+    assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+}
+
+#[test]
+fn readable_if_sent_before() {
+    let (sender, receiver) = oneshot::channel();
+    sender.send(19u128).unwrap();
+    let receiver = receiver.into_pollable().unwrap();
+    assert!(take_readable(&receiver));
+    assert_eq!(receiver.try_recv(), Ok(19));
+}
+
+#[test]
+fn send_from_other_thread() {
+    let (sender, receiver) = oneshot::channel();
+    let receiver = receiver.into_pollable().unwrap();
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        sender.send(19u128).unwrap();
+    });
+    while !take_readable(&receiver) {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(receiver.try_recv(), Ok(19));
+    t.join().unwrap();
+}
+
+#[test]
+fn drop_receiver_while_sending() {
+    for _ in 0..100 {
+        let (sender, receiver) = oneshot::channel();
+        let receiver = receiver.into_pollable().unwrap();
+        let t = thread::spawn(move || sender.send(19u128));
+        mem::drop(receiver);
+        let _ = t.join().unwrap();
+    }
+}
+
+#[cfg(feature = "mio")]
+#[test]
+fn mio_poll() {
+    let mut poll = mio::Poll::new().unwrap();
+    let mut events = mio::Events::with_capacity(4);
+
+    let (sender, receiver) = oneshot::channel();
+    let mut receiver = receiver.into_pollable().unwrap();
+    poll.registry()
+        .register(&mut receiver, mio::Token(7), mio::Interest::READABLE)
+        .unwrap();
+
+    let t = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(10));
+        sender.send(19u128).unwrap();
+    });
+    poll.poll(&mut events, Some(Duration::from_secs(5)))
+        .unwrap();
+    let tokens: Vec<_> = events.iter().map(|event| event.token()).collect();
+    assert_eq!(tokens, [mio::Token(7)]);
+    assert_eq!(receiver.try_recv(), Ok(19));
+
+    poll.registry().deregister(&mut receiver).unwrap();
+    t.join().unwrap();
+}
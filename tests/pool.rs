@@ -0,0 +1,131 @@
+#![cfg(feature = "alloc")]
+
+use oneshot::{ChannelPool, TryRecvError};
+
+#[cfg(feature = "std")]
+use oneshot::RecvError;
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn reuse_allocation() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::new();
+        assert_eq!(pool.idle(), 0);
+        for i in 0..3u64 {
+            let (sender, receiver) = pool.channel();
+            assert_eq!(pool.idle(), 0);
+            assert!(sender.send(i).is_ok());
+            assert_eq!(receiver.try_recv(), Ok(i));
+            drop(receiver);
+            assert_eq!(pool.idle(), 1);
+        }
+    })
+}
+
+#[test]
+fn with_capacity() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::<u8>::with_capacity(2);
+        assert_eq!(pool.idle(), 2);
+
+        let channels = [pool.channel(), pool.channel(), pool.channel()];
+        assert_eq!(pool.idle(), 0);
+        drop(channels);
+        assert_eq!(pool.idle(), 3);
+    })
+}
+
+#[test]
+fn reused_channel_starts_empty() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::new();
+        let (message, counter) = DropCounter::new(());
+
+        let (sender, receiver) = pool.channel();
+        assert!(sender.send(message).is_ok());
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+
+        let (sender, receiver) = pool.channel();
+        assert!(matches!(receiver.try_recv(), Err(TryRecvError::Empty)));
+        assert!(!sender.is_closed());
+        drop(sender);
+        assert!(matches!(
+            receiver.try_recv(),
+            Err(TryRecvError::SenderDropped)
+        ));
+    })
+}
+
+#[test]
+fn send_error_returns_channel_to_pool() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::new();
+        let (sender, receiver) = pool.channel();
+        drop(receiver);
+        let error = sender.send(7u8).unwrap_err();
+        assert_eq!(pool.idle(), 0);
+        assert_eq!(error.into_inner(), 7);
+        assert_eq!(pool.idle(), 1);
+    })
+}
+
+#[test]
+fn drop_pool_with_live_channels() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::with_capacity(1);
+        let (sender, receiver) = pool.channel();
+        let (sender2, receiver2) = pool.channel();
+        drop(pool);
+        assert!(sender.send(1u32).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(1));
+        drop(sender2);
+        drop(receiver2);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn endpoints_return_channels_from_other_threads() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::new();
+        let (sender1, receiver1) = pool.channel();
+        let (sender2, receiver2) = pool.channel();
+        let t1 = thread::spawn(move || sender1.send(1u8).unwrap());
+        let t2 = thread::spawn(move || drop(receiver2));
+
+        assert_eq!(receiver1.recv(), Ok(1));
+        let (sender3, receiver3) = pool.channel();
+        drop(sender2);
+        drop(sender3);
+        assert_eq!(receiver3.recv(), Err(RecvError::SenderDropped));
+
+        t1.join().unwrap();
+        t2.join().unwrap();
+        assert_eq!(pool.idle(), 2);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn drop_pool_while_endpoints_race() {
+    maybe_loom_model(|| {
+        let pool = ChannelPool::<u8>::with_capacity(1);
+        let (sender, receiver) = pool.channel();
+        let t1 = thread::spawn(move || drop(sender));
+        let t2 = thread::spawn(move || drop(pool));
+        assert_eq!(receiver.recv(), Err(RecvError::SenderDropped));
+        t1.join().unwrap();
+        t2.join().unwrap();
+    })
+}
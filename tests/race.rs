@@ -0,0 +1,119 @@
+#![cfg(feature = "alloc")]
+
+use oneshot::{race_channel, RaceSendError, TryRecvError};
+
+#[cfg(feature = "std")]
+use oneshot::RecvError;
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn first_send_wins() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel();
+        let sender2 = sender.clone();
+        assert!(!sender2.is_fulfilled());
+        assert!(sender.send(1u32).is_ok());
+        assert!(sender2.is_fulfilled());
+        assert!(matches!(sender2.send(2), Err(RaceSendError::Fulfilled(2))));
+        assert_eq!(receiver.try_recv(), Ok(1));
+    })
+}
+
+#[test]
+fn disconnected_once_all_senders_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel::<u32>();
+        let sender2 = sender.clone();
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        drop(sender2);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+    })
+}
+
+#[test]
+fn send_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel();
+        let sender2 = sender.clone();
+        drop(receiver);
+        let (message, counter) = DropCounter::new(1u32);
+        let error = sender.send(message).unwrap_err();
+        assert!(matches!(error, RaceSendError::Disconnected(_)));
+        assert_eq!(*error.into_inner().value(), 1);
+        assert_eq!(counter.count(), 1);
+        // The failed send still decided the race.
+        let (message2, _) = DropCounter::new(2u32);
+        assert!(matches!(
+            sender2.send(message2),
+            Err(RaceSendError::Fulfilled(_))
+        ));
+    })
+}
+
+#[test]
+fn message_is_dropped_with_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel();
+        let sender2 = sender.clone();
+        let (message, counter) = DropCounter::new(1u32);
+        sender.send(message).unwrap();
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+        drop(sender2);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn concurrent_senders() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel();
+        let sender2 = sender.clone();
+        let t = thread::spawn(move || sender2.send(2u32).is_ok());
+        let won = sender.send(1).is_ok();
+        let won2 = t.join().unwrap();
+        assert!(won != won2);
+        assert_eq!(receiver.recv(), Ok(if won { 1 } else { 2 }));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn senders_dropped_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel::<u32>();
+        let sender2 = sender.clone();
+        let t = thread::spawn(move || drop(sender2));
+        drop(sender);
+        assert_eq!(receiver.recv(), Err(RecvError::SenderDropped));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn receiver_dropped_while_sending() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = race_channel();
+        let sender2 = sender.clone();
+        let t = thread::spawn(move || drop(receiver));
+        let result = sender.send(1u32);
+        t.join().unwrap();
+        match result {
+            Ok(()) | Err(RaceSendError::Disconnected(1)) => (),
+            Err(error) => panic!("unexpected error: {:?}", error),
+        }
+        drop(sender2);
+    })
+}
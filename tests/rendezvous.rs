@@ -0,0 +1,236 @@
+#![cfg(feature = "std")]
+
+use core::time::Duration;
+use oneshot::rendezvous::{
+    self, RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError,
+};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::{sleep, spawn};
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn send_blocks_until_received() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let t = thread::spawn(move || receiver.recv().map(DropCounter::into_value));
+        let (message, counter) = DropCounter::new(19u128);
+        sender.send(message).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[test]
+fn recv_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let t = thread::spawn(move || {
+            #[cfg(not(loom))]
+            thread::sleep(Duration::from_millis(2));
+            sender.send(19u128)
+        });
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    })
+}
+
+#[test]
+fn try_recv_without_sending_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel::<u128>();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        drop(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+    })
+}
+
+#[test]
+fn send_timeout_without_receiving_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let (message, counter) = DropCounter::new(19u128);
+        match sender.send_timeout(message, Duration::from_millis(1)) {
+            Err(SendTimeoutError::Timeout(message)) => assert_eq!(*message.value(), 19),
+            _ => panic!("expected a timeout"),
+        }
+        assert_eq!(counter.count(), 1);
+        // The message was withdrawn from the channel again.
+        assert_eq!(receiver.try_recv().err(), Some(TryRecvError::Empty));
+    })
+}
+
+#[test]
+fn recv_timeout_without_sending_sender() {
+    maybe_loom_model(|| {
+        let (_sender, receiver) = rendezvous::channel::<u128>();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    })
+}
+
+#[test]
+fn send_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        drop(receiver);
+        assert_eq!(sender.send(19u128), Err(SendError(19)));
+        assert!(matches!(
+            sender.send_timeout(19u128, Duration::from_millis(1)),
+            Err(SendTimeoutError::Disconnected(19))
+        ));
+    })
+}
+
+#[test]
+fn drop_receiver_while_sending() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let t = thread::spawn(move || drop(receiver));
+        let (message, counter) = DropCounter::new(19u128);
+        let error = sender.send(message).unwrap_err();
+        assert_eq!(*error.into_inner().value(), 19);
+        assert_eq!(counter.count(), 1);
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn drop_sender_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel::<u128>();
+        let t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn send_timeout_racing_recv_timeout() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let t = thread::spawn(move || receiver.recv_timeout(Duration::from_millis(1)));
+        let sent = sender.send_timeout(19u128, Duration::from_millis(1));
+        // Either the handoff happened, or neither side saw the other.
+        match (sent, t.join().unwrap()) {
+            (Ok(()), Ok(19)) => (),
+            (Err(SendTimeoutError::Timeout(19)), Err(RecvTimeoutError::Timeout)) => (),
+            results => panic!("inconsistent results: {:?}", results),
+        }
+    })
+}
+
+#[test]
+fn cloned_senders_and_receivers() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = rendezvous::channel();
+        let (sender2, receiver2) = (sender.clone(), receiver.clone());
+        let t1 = thread::spawn(move || sender.send(1u32).unwrap());
+        let t2 = thread::spawn(move || sender2.send(2u32).unwrap());
+        let received = receiver.recv().unwrap() + receiver2.recv().unwrap();
+        assert_eq!(received, 3);
+        t1.join().unwrap();
+        t2.join().unwrap();
+
+        // The channel is disconnected once all clones of the senders are gone.
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        drop(receiver);
+        assert_eq!(receiver2.try_recv(), Err(TryRecvError::Disconnected));
+    })
+}
+
+#[cfg(not(loom))]
+#[test]
+fn many_senders_and_receivers() {
+    let (sender, receiver) = rendezvous::channel();
+    let senders: Vec<_> = (0..4u64)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || (0..100).for_each(|j| sender.send(i * 100 + j).unwrap()))
+        })
+        .collect();
+    drop(sender);
+    let receivers: Vec<_> = (0..3)
+        .map(|_| {
+            let receiver = receiver.clone();
+            thread::spawn(move || receiver.recv().unwrap_or(0))
+        })
+        .collect();
+
+    let mut sum = 0;
+    while let Ok(message) = receiver.recv() {
+        sum += message;
+    }
+    // Every message was taken by exactly one of the receivers.
+    sum += receivers
+        .into_iter()
+        .map(|t| t.join().unwrap())
+        .sum::<u64>();
+    senders.into_iter().for_each(|t| t.join().unwrap());
+    assert_eq!(sum, (0..400).sum());
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+mod asynchronous {
+    use super::*;
+
+    #[tokio::test]
+    async fn send_async_to_thread() {
+        let (sender, receiver) = rendezvous::channel();
+        let t = std::thread::spawn(move || receiver.recv());
+        sender.send_async(19u128).await.unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+    }
+
+    #[tokio::test]
+    async fn recv_async_from_thread() {
+        let (sender, receiver) = rendezvous::channel();
+        let t = std::thread::spawn(move || sender.send(19u128));
+        assert_eq!(receiver.recv_async().await, Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn send_async_to_recv_async() {
+        let (sender, receiver) = rendezvous::channel();
+        let (sent, received) = tokio::join!(sender.send_async(19u128), receiver.recv_async());
+        assert_eq!(sent, Ok(()));
+        assert_eq!(received, Ok(19));
+    }
+
+    #[tokio::test]
+    async fn recv_async_with_dropped_sender() {
+        let (sender, receiver) = rendezvous::channel::<u128>();
+        let t = std::thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv_async().await, Err(RecvError));
+        t.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropping_send_future_withdraws_message() {
+        let (sender, receiver) = rendezvous::channel();
+        let (message, counter) = DropCounter::new(19u128);
+        let timeout = tokio::time::timeout(Duration::from_millis(1), sender.send_async(message));
+        assert!(timeout.await.is_err());
+        assert_eq!(counter.count(), 1);
+        assert_eq!(receiver.try_recv().err(), Some(TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn dropping_recv_future_loses_no_message() {
+        let (sender, receiver) = rendezvous::channel();
+        let timeout = tokio::time::timeout(Duration::from_millis(1), receiver.recv_async());
+        assert!(timeout.await.is_err());
+        let t = std::thread::spawn(move || sender.send(19u128));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+}
@@ -0,0 +1,155 @@
+#![cfg(feature = "alloc")]
+
+use oneshot::{ReusableChannel, TryRecvError};
+
+#[cfg(feature = "std")]
+use oneshot::RecvError;
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn reuse_after_try_recv() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+        for i in 0..3u64 {
+            let (sender, receiver) = reusable.channel().unwrap();
+            assert!(sender.send(i).is_ok());
+            assert_eq!(receiver.try_recv(), Ok(i));
+            assert!(reusable.is_in_use());
+            drop(receiver);
+            assert!(!reusable.is_in_use());
+        }
+    })
+}
+
+#[test]
+fn no_new_pair_while_in_use() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::<u8>::new();
+        let (sender, receiver) = reusable.channel().unwrap();
+        assert!(reusable.channel().is_none());
+
+        drop(sender);
+        assert!(reusable.channel().is_none());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        drop(receiver);
+
+        let (sender, receiver) = reusable.channel().unwrap();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert!(!sender.is_closed());
+    })
+}
+
+#[test]
+fn no_new_pair_while_any_old_endpoint_is_alive() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+
+        // The receiver is kept after taking the message.
+        let (sender, receiver) = reusable.channel().unwrap();
+        assert!(sender.send(1u8).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(1));
+        assert!(reusable.channel().is_none());
+        drop(receiver);
+
+        // The sender is kept after the receiver is gone.
+        let (sender, receiver) = reusable.channel().unwrap();
+        drop(receiver);
+        assert!(sender.is_closed());
+        assert!(reusable.channel().is_none());
+
+        // The send error is kept, holding the message in the channel.
+        let error = sender.send(2).unwrap_err();
+        assert!(reusable.channel().is_none());
+        assert_eq!(error.into_inner(), 2);
+
+        assert!(reusable.channel().is_some());
+    })
+}
+
+#[test]
+fn reuse_after_send_error() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+        let (message, counter) = DropCounter::new(());
+
+        let (sender, receiver) = reusable.channel().unwrap();
+        drop(receiver);
+        let error = sender.send(message).unwrap_err();
+        assert!(reusable.is_in_use());
+        assert_eq!(counter.count(), 0);
+        drop(error);
+        assert_eq!(counter.count(), 1);
+
+        let (sender, receiver) = reusable.channel().unwrap();
+        assert!(sender.send(DropCounter::new(()).0).is_ok());
+        drop(receiver);
+        assert!(!reusable.is_in_use());
+    })
+}
+
+#[test]
+fn unreceived_message_dropped_before_reuse() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+        let (message, counter) = DropCounter::new(());
+
+        let (sender, receiver) = reusable.channel().unwrap();
+        assert!(sender.send(message).is_ok());
+        assert_eq!(counter.count(), 0);
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+
+        let (_sender, receiver) = reusable.channel().unwrap();
+        assert!(matches!(receiver.try_recv(), Err(TryRecvError::Empty)));
+    })
+}
+
+#[test]
+fn drop_reusable_with_live_endpoints() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+        let (sender, receiver) = reusable.channel().unwrap();
+        drop(reusable);
+        assert!(sender.send(5u32).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(5));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reuse_across_threads() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::new();
+        for i in 0..2u8 {
+            let (sender, receiver) = reusable.channel().unwrap();
+            let t = thread::spawn(move || sender.send(i).unwrap());
+            assert_eq!(receiver.recv(), Ok(i));
+            t.join().unwrap();
+            assert!(!reusable.is_in_use());
+        }
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn drop_reusable_while_endpoints_race() {
+    maybe_loom_model(|| {
+        let mut reusable = ReusableChannel::<u8>::new();
+        let (sender, receiver) = reusable.channel().unwrap();
+        let t1 = thread::spawn(move || drop(sender));
+        let t2 = thread::spawn(move || drop(reusable));
+        assert_eq!(receiver.recv(), Err(RecvError::SenderDropped));
+        t1.join().unwrap();
+        t2.join().unwrap();
+    })
+}
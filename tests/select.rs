@@ -0,0 +1,344 @@
+#![cfg(feature = "std")]
+
+use core::mem;
+use oneshot::{RecvError, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::{sleep, spawn};
+
+    #[cfg(loom)]
+    pub fn sleep(_timeout: core::time::Duration) {
+        loom::thread::yield_now()
+    }
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn already_sent() {
+    maybe_loom_model(|| {
+        let (_sender1, receiver1) = oneshot::channel::<u32>();
+        let (sender2, receiver2) = oneshot::channel();
+        let (sender3, receiver3) = oneshot::channel();
+        sender2.send(2).unwrap();
+        sender3.send(3).unwrap();
+
+        // The lowest ready index wins, the other message stays in its channel.
+        assert_eq!(
+            oneshot::select(&[&receiver1, &receiver2, &receiver3]),
+            (1, Ok(2))
+        );
+        assert_eq!(receiver2.try_recv(), Err(TryRecvError::SenderDropped));
+        assert_eq!(receiver3.try_recv(), Ok(3));
+    })
+}
+
+#[test]
+fn already_disconnected() {
+    maybe_loom_model(|| {
+        let (_sender1, receiver1) = oneshot::channel::<u32>();
+        let (sender2, receiver2) = oneshot::channel::<u32>();
+        mem::drop(sender2);
+
+        assert_eq!(
+            oneshot::select(&[&receiver1, &receiver2]),
+            (1, Err(RecvError::SenderDropped))
+        );
+    })
+}
+
+#[test]
+fn send_while_selecting() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender2.send(9).unwrap();
+        });
+
+        assert_eq!(oneshot::select(&[&receiver1, &receiver2]), (1, Ok(9)));
+        t.join().unwrap();
+
+        // The first channel is left usable after the select.
+        assert_eq!(receiver1.try_recv(), Err(TryRecvError::Empty));
+        sender1.send(8).unwrap();
+        assert_eq!(receiver1.recv(), Ok(8));
+    })
+}
+
+#[test]
+fn drop_sender_while_selecting() {
+    maybe_loom_model(|| {
+        let (_sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            mem::drop(sender2);
+        });
+
+        assert_eq!(
+            oneshot::select(&[&receiver1, &receiver2]),
+            (1, Err(RecvError::SenderDropped))
+        );
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn send_on_all_while_selecting() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u32>();
+        let (sender2, receiver2) = oneshot::channel::<u32>();
+        let t1 = thread::spawn(move || sender1.send(1).unwrap());
+        let t2 = thread::spawn(move || sender2.send(2).unwrap());
+
+        let (index, result) = oneshot::select(&[&receiver1, &receiver2]);
+        t1.join().unwrap();
+        t2.join().unwrap();
+
+        // Whichever receiver lost the race still has its message.
+        match index {
+            0 => {
+                assert_eq!(result, Ok(1));
+                assert_eq!(receiver2.try_recv(), Ok(2));
+            }
+            1 => {
+                assert_eq!(result, Ok(2));
+                assert_eq!(receiver1.try_recv(), Ok(1));
+            }
+            _ => unreachable!(),
+        }
+    })
+}
+
+#[test]
+fn deadline_passed() {
+    maybe_loom_model(|| {
+        let (_sender1, receiver1) = oneshot::channel::<u128>();
+        let (_sender2, receiver2) = oneshot::channel::<u128>();
+
+        let start = Instant::now();
+        assert_eq!(
+            oneshot::select_deadline(&[&receiver1, &receiver2], start),
+            None
+        );
+        assert_eq!(
+            oneshot::select_timeout(&[&receiver1, &receiver2], Duration::from_millis(0)),
+            None
+        );
+        assert!(start.elapsed() < Duration::from_millis(200));
+    })
+}
+
+#[test]
+fn deadline_then_send() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+
+        let timeout = Duration::from_millis(1);
+        let start = Instant::now();
+        assert_eq!(
+            oneshot::select_timeout(&[&receiver1, &receiver2], timeout),
+            None
+        );
+        assert!(start.elapsed() >= timeout);
+
+        // No waker is left behind in the channels after a timeout.
+        sender1.send(1).unwrap();
+        sender2.send(2).unwrap();
+        assert_eq!(receiver1.recv(), Ok(1));
+        assert_eq!(receiver2.recv(), Ok(2));
+    })
+}
+
+#[test]
+fn send_before_deadline() {
+    maybe_loom_model(|| {
+        let (_sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender2.send(9).unwrap();
+        });
+
+        assert_eq!(
+            oneshot::select_timeout(&[&receiver1, &receiver2], Duration::from_secs(1)),
+            Some((1, Ok(9)))
+        );
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn no_receivers_with_deadline() {
+    maybe_loom_model(|| {
+        let receivers: [&oneshot::Receiver<u8>; 0] = [];
+        let timeout = Duration::from_millis(1);
+        let start = Instant::now();
+        assert_eq!(oneshot::select_timeout(&receivers, timeout), None);
+        assert!(start.elapsed() >= timeout);
+    })
+}
+
+#[test]
+#[should_panic]
+fn no_receivers() {
+    let receivers: [&oneshot::Receiver<u8>; 0] = [];
+    let _ = oneshot::select(&receivers);
+}
+
+#[test]
+fn same_receiver_twice() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender.send(9).unwrap();
+        });
+
+        let (index, result) = oneshot::select(&[&receiver, &receiver]);
+        assert!(index < 2);
+        assert_eq!(result, Ok(9));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn message_dropped_with_losing_receiver() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel();
+        let (sender2, receiver2) = oneshot::channel();
+        let (message1, counter1) = DropCounter::new(());
+        let (message2, counter2) = DropCounter::new(());
+        sender1.send(message1).unwrap();
+        sender2.send(message2).unwrap();
+
+        let (index, result) = oneshot::select(&[&receiver1, &receiver2]);
+        assert_eq!(index, 0);
+        mem::drop(result);
+        assert_eq!(counter1.count(), 1);
+        assert_eq!(counter2.count(), 0);
+
+        mem::drop(receiver2);
+        assert_eq!(counter2.count(), 1);
+        mem::drop(receiver1);
+    })
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+#[test]
+fn select_after_poll() {
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{self, Poll};
+
+    let (sender1, mut receiver1) = oneshot::channel::<u32>();
+    let (_sender2, receiver2) = oneshot::channel::<u32>();
+
+    let (waker, wakeups) = helpers::counting_waker();
+    let mut context = task::Context::from_waker(&waker);
+    assert_eq!(Pin::new(&mut receiver1).poll(&mut context), Poll::Pending);
+
+    let t = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(2));
+        sender1.send(1).unwrap();
+    });
+    assert_eq!(oneshot::select(&[&receiver1, &receiver2]), (0, Ok(1)));
+    t.join().unwrap();
+
+    // The task waker was replaced by the select, so it was never woken.
+    assert_eq!(wakeups.load(core::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[test]
+fn gather_already_done() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u32>();
+        let (sender2, receiver2) = oneshot::channel::<u32>();
+        sender1.send(1).unwrap();
+        mem::drop(sender2);
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert_eq!(
+            oneshot::gather(&[&receiver1, &receiver2], deadline),
+            [Ok(1), Err(RecvTimeoutError::SenderDropped)]
+        );
+    })
+}
+
+#[test]
+fn gather_from_threads() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+        let (sender3, receiver3) = oneshot::channel::<u128>();
+        let t1 = thread::spawn(move || sender1.send(1).unwrap());
+        let t2 = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender2.send(2).unwrap();
+            mem::drop(sender3);
+        });
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        assert_eq!(
+            oneshot::gather(&[&receiver1, &receiver2, &receiver3], deadline),
+            [Ok(1), Ok(2), Err(RecvTimeoutError::SenderDropped)]
+        );
+        t1.join().unwrap();
+        t2.join().unwrap();
+    })
+}
+
+#[test]
+fn gather_deadline_passed() {
+    maybe_loom_model(|| {
+        let (sender1, receiver1) = oneshot::channel::<u128>();
+        let (sender2, receiver2) = oneshot::channel::<u128>();
+        sender1.send(1).unwrap();
+
+        let timeout = Duration::from_millis(1);
+        let start = Instant::now();
+        assert_eq!(
+            oneshot::gather(&[&receiver1, &receiver2], start + timeout),
+            [Ok(1), Err(RecvTimeoutError::Timeout)]
+        );
+        assert!(start.elapsed() >= timeout);
+
+        // No waker is left behind in the channel that timed out.
+        sender2.send(2).unwrap();
+        assert_eq!(receiver2.recv(), Ok(2));
+    })
+}
+
+#[test]
+fn gather_no_receivers() {
+    maybe_loom_model(|| {
+        let receivers: [&oneshot::Receiver<u8>; 0] = [];
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert!(oneshot::gather(&receivers, deadline).is_empty());
+    })
+}
+
+#[test]
+fn gather_same_receiver_twice() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || sender.send(9).unwrap());
+
+        let deadline = Instant::now() + Duration::from_secs(1);
+        // Only one of the two gets the message, which one depends on when it arrived.
+        let results = oneshot::gather(&[&receiver, &receiver], deadline);
+        assert!(
+            results == [Ok(9), Err(RecvTimeoutError::SenderDropped)]
+                || results == [Err(RecvTimeoutError::SenderDropped), Ok(9)]
+        );
+        t.join().unwrap();
+    })
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "std")]
+
+use core::time::Duration;
+use oneshot::shared::SendError;
+use oneshot::{RecvError, RecvTimeoutError, TryRecvError};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::maybe_loom_model;
+
+#[test]
+fn every_receiver_gets_the_message() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel();
+        let receiver2 = receiver.clone();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        sender.send(19u128).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(19));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(receiver2.recv_timeout(Duration::from_millis(1)), Ok(19));
+        assert_eq!(receiver.clone().try_recv(), Ok(19));
+    })
+}
+
+#[test]
+fn recv_timeout_before_send() {
+    maybe_loom_model(|| {
+        let (_sender, receiver) = oneshot::shared_channel::<u32>();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    })
+}
+
+#[test]
+fn recv_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel();
+        let receiver2 = receiver.clone();
+        let t1 = thread::spawn(move || receiver.recv());
+        let t2 = thread::spawn(move || receiver2.recv());
+        sender.send(19u128).unwrap();
+        assert_eq!(t1.join().unwrap(), Ok(19));
+        assert_eq!(t2.join().unwrap(), Ok(19));
+    })
+}
+
+#[test]
+fn drop_sender_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel::<u32>();
+        let receiver2 = receiver.clone();
+        let t = thread::spawn(move || receiver2.recv());
+        drop(sender);
+        assert_eq!(receiver.recv(), Err(RecvError::SenderDropped));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        assert_eq!(t.join().unwrap(), Err(RecvError::SenderDropped));
+    })
+}
+
+#[test]
+fn recv_with_panicked_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel::<u32>();
+        let receiver2 = receiver.clone();
+        let t = thread::spawn(move || receiver2.recv());
+        helpers::drop_while_panicking(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderPanicked));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::SenderPanicked)
+        );
+        assert_eq!(t.join().unwrap(), Err(RecvError::SenderPanicked));
+    })
+}
+
+#[test]
+fn send_with_dropped_receivers() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel();
+        let receiver2 = receiver.clone();
+        drop(receiver);
+        assert!(!sender.is_closed());
+        drop(receiver2);
+        assert!(sender.is_closed());
+        assert_eq!(sender.send(19u128), Err(SendError(19)));
+    })
+}
+
+#[test]
+fn message_is_dropped_with_last_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::shared_channel();
+        let message = std::sync::Arc::new(19u32);
+        sender.send(message.clone()).unwrap();
+        let receiver2 = receiver.clone();
+        drop(receiver);
+        assert_eq!(std::sync::Arc::strong_count(&message), 2);
+        drop(receiver2);
+        assert_eq!(std::sync::Arc::strong_count(&message), 1);
+    })
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+mod asynchronous {
+    use super::*;
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    #[tokio::test]
+    async fn threads_and_tasks_wait_together() {
+        let (sender, receiver) = oneshot::shared_channel();
+        let thread_receiver = receiver.clone();
+        let t = std::thread::spawn(move || thread_receiver.recv());
+        let tasks: Vec<_> = (0..4).map(|_| tokio::spawn(receiver.clone())).collect();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        sender.send("done").unwrap();
+        for task in tasks {
+            assert_eq!(task.await.unwrap(), Ok("done"));
+        }
+        assert_eq!(receiver.await, Ok("done"));
+        assert_eq!(t.join().unwrap(), Ok("done"));
+    }
+
+    #[tokio::test]
+    async fn await_dropped_sender() {
+        let (sender, receiver) = oneshot::shared_channel::<u32>();
+        let task = tokio::spawn(receiver);
+        drop(sender);
+        assert_eq!(task.await.unwrap(), Err(RecvError::SenderDropped));
+    }
+
+    #[test]
+    fn poll_wakes_latest_waker() {
+        let (sender, mut receiver) = oneshot::shared_channel();
+        let (waker1, wakeups1) = helpers::counting_waker();
+        let (waker2, wakeups2) = helpers::counting_waker();
+        let poll1 = Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker1));
+        let poll2 = Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker2));
+        assert_eq!((poll1, poll2), (Poll::Pending, Poll::Pending));
+
+        sender.send(19u32).unwrap();
+        assert_eq!(wakeups1.load(core::sync::atomic::Ordering::SeqCst), 0);
+        assert_eq!(wakeups2.load(core::sync::atomic::Ordering::SeqCst), 1);
+        let poll = Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker2));
+        assert_eq!(poll, Poll::Ready(Ok(19)));
+    }
+
+    #[test]
+    fn blocking_recv_after_poll() {
+        let (sender, mut receiver) = oneshot::shared_channel();
+        let (waker, _) = helpers::counting_waker();
+        let poll = Pin::new(&mut receiver).poll(&mut Context::from_waker(&waker));
+        assert_eq!(poll, Poll::Pending);
+        let t = std::thread::spawn(move || sender.send(19u32));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+}
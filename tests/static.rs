@@ -0,0 +1,198 @@
+use oneshot::{ChannelState, StaticChannel, TryRecvError};
+
+#[cfg(feature = "std")]
+use oneshot::RecvError;
+
+#[cfg(feature = "std")]
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+/// Creates a channel living for the rest of the program, so its endpoints can be moved to
+/// other threads.
+#[cfg(feature = "std")]
+fn leaked_channel<T>() -> &'static StaticChannel<T> {
+    Box::leak(Box::new(StaticChannel::new()))
+}
+
+#[cfg(not(loom))]
+#[test]
+fn in_static() {
+    static CHANNEL: StaticChannel<u32> = StaticChannel::new();
+
+    for i in 0..3 {
+        let (sender, receiver) = CHANNEL.channel().unwrap();
+        assert!(CHANNEL.is_in_use());
+        assert!(sender.send(i).is_ok());
+        assert_eq!(receiver.try_recv(), Ok(i));
+        drop(receiver);
+        assert!(!CHANNEL.is_in_use());
+    }
+}
+
+#[test]
+fn no_new_pair_while_in_use() {
+    maybe_loom_model(|| {
+        let channel = StaticChannel::<u8>::new();
+        let (sender, receiver) = channel.channel().unwrap();
+        assert!(channel.channel().is_none());
+
+        drop(sender);
+        assert!(channel.channel().is_none());
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        drop(receiver);
+
+        let (sender, receiver) = channel.channel().unwrap();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+        assert!(!sender.is_closed());
+    })
+}
+
+#[test]
+fn message_dropped_with_receiver() {
+    maybe_loom_model(|| {
+        let channel = StaticChannel::new();
+        let (message, counter) = DropCounter::new(());
+        let (sender, receiver) = channel.channel().unwrap();
+        assert!(sender.send(message).is_ok());
+        assert_eq!(counter.count(), 0);
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[test]
+fn close_returns_message() {
+    maybe_loom_model(|| {
+        let channel = StaticChannel::new();
+        let (sender, receiver) = channel.channel().unwrap();
+        assert!(sender.send(5u8).is_ok());
+        assert_eq!(receiver.close(), Some(5));
+        assert!(!channel.is_in_use());
+
+        let (sender, receiver) = channel.channel().unwrap();
+        assert_eq!(receiver.close(), None);
+        assert!(channel.is_in_use());
+        assert_eq!(sender.send(6).unwrap_err().into_inner(), 6);
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[test]
+fn channel_state() {
+    maybe_loom_model(|| {
+        let channel = StaticChannel::new();
+        let (sender, receiver) = channel.channel().unwrap();
+        assert_eq!(sender.state(), ChannelState::Empty);
+        sender.send(5u8).unwrap();
+        assert!(receiver.has_message());
+        assert_eq!(receiver.try_recv(), Ok(5));
+        assert!(receiver.is_closed());
+        assert_eq!(
+            format!("{:?}", receiver),
+            "Receiver { state: Disconnected, .. }"
+        );
+    })
+}
+
+#[test]
+fn send_error() {
+    maybe_loom_model(|| {
+        let channel = StaticChannel::new();
+
+        let (sender, receiver) = channel.channel().unwrap();
+        drop(receiver);
+        assert!(sender.is_closed());
+        let error = sender.send(5u8).unwrap_err();
+        assert_eq!(*error.as_inner(), 5);
+        assert!(channel.is_in_use());
+        assert_eq!(error.into_inner(), 5);
+        assert!(!channel.is_in_use());
+
+        let channel = StaticChannel::new();
+        let (message, counter) = DropCounter::new(());
+        let (sender, receiver) = channel.channel().unwrap();
+        drop(receiver);
+        drop(sender.send(message).unwrap_err());
+        assert_eq!(counter.count(), 1);
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_across_threads() {
+    maybe_loom_model(|| {
+        let channel = leaked_channel();
+        for i in 0..2u8 {
+            let (sender, receiver) = channel.channel().unwrap();
+            let t = thread::spawn(move || sender.send(i).unwrap());
+            assert_eq!(receiver.recv(), Ok(i));
+            t.join().unwrap();
+            assert!(!channel.is_in_use());
+        }
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_with_strategy_across_threads() {
+    maybe_loom_model(|| {
+        let channel = leaked_channel();
+        let (sender, receiver) = channel.channel().unwrap();
+        let t = thread::spawn(move || sender.send(5u8).unwrap());
+        let strategy = oneshot::RecvStrategy::new().spin(2).yield_now(2);
+        assert_eq!(receiver.recv_with_strategy(strategy), Ok(5));
+        t.join().unwrap();
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_ref_with_dropped_sender() {
+    maybe_loom_model(|| {
+        let channel = leaked_channel::<u8>();
+        let (sender, receiver) = channel.channel().unwrap();
+        let t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv_ref(), Err(RecvError::SenderDropped));
+        t.join().unwrap();
+        drop(receiver);
+        assert!(!channel.is_in_use());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_closed() {
+    maybe_loom_model(|| {
+        let channel = leaked_channel::<u8>();
+        let (sender, receiver) = channel.channel().unwrap();
+        let t = thread::spawn(move || drop(receiver));
+        sender.wait_closed();
+        assert!(sender.is_closed());
+        t.join().unwrap();
+    })
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+#[tokio::test]
+async fn await_message() {
+    static CHANNEL: StaticChannel<u32> = StaticChannel::new();
+
+    let (sender, receiver) = CHANNEL.channel().unwrap();
+    let t = tokio::spawn(async move {
+        tokio::task::yield_now().await;
+        sender.send(9).unwrap();
+    });
+    assert_eq!(receiver.await, Ok(9));
+    t.await.unwrap();
+    assert!(!CHANNEL.is_in_use());
+}
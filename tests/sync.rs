@@ -1,5 +1,7 @@
+#![cfg(feature = "alloc")]
+
 use core::mem;
-use oneshot::TryRecvError;
+use oneshot::{ChannelState, TryRecvError};
 
 #[cfg(feature = "std")]
 use oneshot::{RecvError, RecvTimeoutError};
@@ -29,10 +31,10 @@ fn send_before_try_recv() {
         assert!(sender.send(19i128).is_ok());
 
         assert_eq!(receiver.try_recv(), Ok(19i128));
-        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
         #[cfg(feature = "std")]
         {
-            assert_eq!(receiver.recv_ref(), Err(RecvError));
+            assert_eq!(receiver.recv_ref(), Err(RecvError::SenderDropped));
             assert!(receiver.recv_timeout(Duration::from_secs(1)).is_err());
         }
     })
@@ -75,8 +77,8 @@ fn send_before_recv_ref() {
         assert!(sender.send(19i128).is_ok());
 
         assert_eq!(receiver.recv_ref(), Ok(19i128));
-        assert_eq!(receiver.recv_ref(), Err(RecvError));
-        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        assert_eq!(receiver.recv_ref(), Err(RecvError::SenderDropped));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
         assert!(receiver.recv_timeout(Duration::from_secs(1)).is_err());
     })
 }
@@ -99,6 +101,17 @@ fn send_before_recv_timeout() {
     })
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn send_before_recv_with_strategy() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        assert!(sender.send(19u128).is_ok());
+        let strategy = oneshot::RecvStrategy::new().spin(2);
+        assert_eq!(receiver.recv_with_strategy(strategy), Ok(19));
+    })
+}
+
 #[test]
 fn send_then_drop_receiver() {
     maybe_loom_model(|| {
@@ -166,6 +179,49 @@ fn recv_timeout_before_send() {
     })
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn recv_with_strategy_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender.send(9u128).unwrap();
+        });
+        // Busy-waiting gives up long before the message arrives, so this ends up blocking.
+        let strategy = oneshot::RecvStrategy::new().spin(2).yield_now(2);
+        assert_eq!(receiver.recv_with_strategy(strategy), Ok(9));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_with_strategy_while_sending() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || sender.send(9u128).unwrap());
+        let strategy = oneshot::RecvStrategy::new().spin(3).yield_now(3);
+        assert_eq!(receiver.recv_with_strategy(strategy), Ok(9));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_with_strategy_then_drop_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || mem::drop(sender));
+        let strategy = oneshot::RecvStrategy::new().spin(3).yield_now(3);
+        assert_eq!(
+            receiver.recv_with_strategy(strategy),
+            Err(RecvError::SenderDropped)
+        );
+        t.join().unwrap();
+    })
+}
+
 #[cfg(feature = "std")]
 #[test]
 fn recv_before_send_then_drop_sender() {
@@ -194,6 +250,152 @@ fn recv_timeout_before_send_then_drop_sender() {
     })
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn recv_with_panicked_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        helpers::drop_while_panicking(sender);
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderPanicked));
+        assert_eq!(receiver.recv_ref(), Err(RecvError::SenderPanicked));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::SenderPanicked)
+        );
+        assert_eq!(receiver.recv(), Err(RecvError::SenderPanicked));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_before_send_then_panic_in_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            helpers::drop_while_panicking(sender);
+        });
+        assert_eq!(receiver.recv(), Err(RecvError::SenderPanicked));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_timeout_before_send_then_panic_in_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            helpers::drop_while_panicking(sender);
+        });
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(1)),
+            Err(RecvTimeoutError::SenderPanicked)
+        );
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn panic_in_sender_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || mem::drop(receiver));
+        helpers::drop_while_panicking(sender);
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_leaves_message_in_channel() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender.send(9u128).unwrap();
+        });
+        receiver.wait();
+        assert_eq!(receiver.try_recv(), Ok(9));
+        receiver.wait();
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_timeout_then_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        assert!(!receiver.wait_timeout(Duration::from_millis(1)));
+        sender.send(9u128).unwrap();
+        assert!(receiver.wait_timeout(Duration::from_millis(1)));
+        assert_eq!(receiver.recv(), Ok(9));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_deadline_then_drop_sender() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || mem::drop(sender));
+        assert!(receiver.wait_deadline(Instant::now() + Duration::from_secs(1)));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::SenderDropped));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn channel_state() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        assert_eq!(sender.state(), ChannelState::Empty);
+        assert_eq!(receiver.state(), ChannelState::Empty);
+        assert!(!receiver.has_message());
+        assert!(!receiver.is_closed());
+
+        sender.send(19).unwrap();
+        assert_eq!(receiver.state(), ChannelState::Message);
+        assert!(receiver.has_message());
+        assert!(!receiver.is_closed());
+
+        assert_eq!(receiver.try_recv(), Ok(19));
+        assert_eq!(receiver.state(), ChannelState::Disconnected);
+        assert!(!receiver.has_message());
+        assert!(receiver.is_closed());
+
+        let (sender, receiver) = oneshot::channel::<u128>();
+        mem::drop(receiver);
+        assert_eq!(sender.state(), ChannelState::Disconnected);
+    })
+}
+
+#[cfg(not(loom))]
+#[test]
+fn debug_shows_channel_state() {
+    let (sender, receiver) = oneshot::channel::<u128>();
+    assert_eq!(format!("{:?}", sender), "Sender { state: Empty, .. }");
+    sender.send(19).unwrap();
+    assert_eq!(format!("{:?}", receiver), "Receiver { state: Message, .. }");
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+#[test]
+fn sender_sees_blocking_receiver() {
+    let (sender, receiver) = oneshot::channel::<u128>();
+    let t = thread::spawn(move || receiver.recv());
+    while sender.state() == ChannelState::Empty {
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(sender.state(), ChannelState::Receiving { sync: true });
+    sender.send(19).unwrap();
+    assert_eq!(t.join().unwrap(), Ok(19));
+}
+
 #[test]
 fn try_recv() {
     maybe_loom_model(|| {
@@ -253,6 +455,39 @@ fn recv_deadline_and_timeout_time_should_elapse() {
     })
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn recv_deadline_far_in_the_future() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            sender.send(9u128).unwrap();
+        });
+        // Too far away for a 32 bit timespec.
+        let deadline = Instant::now() + Duration::from_secs(u64::from(u32::MAX) * 4);
+        assert_eq!(receiver.recv_deadline(deadline), Ok(9));
+        t.join().unwrap();
+    })
+}
+
+/// Unparking the receiving thread from the outside must not make the receiver return early.
+#[cfg(all(feature = "std", not(loom)))]
+#[test]
+fn recv_ignores_unpark() {
+    let (sender, receiver) = oneshot::channel();
+    let receiving_thread = std::thread::current();
+    let t = std::thread::spawn(move || {
+        for _ in 0..5 {
+            std::thread::sleep(Duration::from_millis(2));
+            receiving_thread.unpark();
+        }
+        sender.send(9u128).unwrap();
+    });
+    assert_eq!(receiver.recv_timeout(Duration::from_secs(10)), Ok(9));
+    t.join().unwrap();
+}
+
 #[cfg(not(loom))]
 #[test]
 fn non_send_type_can_be_used_on_same_thread() {
@@ -280,6 +515,67 @@ fn message_in_channel_dropped_on_receiver_drop() {
     })
 }
 
+#[test]
+fn close_returns_message() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(());
+        sender.send(message).unwrap();
+        let message = receiver.close().unwrap();
+        assert_eq!(counter.count(), 0);
+        mem::drop(message);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+#[test]
+fn close_then_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        assert!(receiver.close().is_none());
+        assert!(sender.is_closed());
+        assert_eq!(sender.send(5).unwrap_err().into_inner(), 5);
+    })
+}
+
+#[test]
+fn close_after_receive() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        sender.send(5).unwrap();
+        assert_eq!(receiver.try_recv(), Ok(5));
+        assert_eq!(receiver.close(), None);
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn close_while_sending() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || sender.send(5).map_err(|error| error.into_inner()));
+
+        // The message ends up on exactly one side.
+        let closed = receiver.close();
+        let sent = t.join().unwrap();
+        match (closed, sent) {
+            (Some(5), Ok(())) | (None, Err(5)) => (),
+            other => panic!("unexpected outcome: {other:?}"),
+        }
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn close_with_acked_message() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let delivery = sender.send_acked(5).unwrap();
+        assert_eq!(receiver.close(), Some(5));
+        assert_eq!(delivery.wait(), Ok(()));
+    })
+}
+
 #[test]
 fn send_error_drops_message_correctly() {
     maybe_loom_model(|| {
@@ -307,3 +603,73 @@ fn send_error_drops_message_correctly_on_into_inner() {
         assert_eq!(counter.count(), 1);
     });
 }
+
+#[test]
+fn sender_is_closed() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        assert!(!sender.is_closed());
+        mem::drop(receiver);
+        assert!(sender.is_closed());
+        assert!(sender.send(5).is_err());
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_closed_before_drop_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            mem::drop(receiver);
+        });
+        sender.wait_closed();
+        assert!(sender.is_closed());
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_closed_timeout_before_drop_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(2));
+            mem::drop(receiver);
+        });
+        assert!(sender.wait_closed_timeout(Duration::from_secs(1)));
+        t.join().unwrap();
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_closed_timeout_then_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+
+        let start = Instant::now();
+        #[cfg(not(loom))]
+        let timeout = Duration::from_millis(100);
+        #[cfg(loom)]
+        let timeout = Duration::from_millis(1);
+        assert!(!sender.wait_closed_timeout(timeout));
+        assert!(start.elapsed() > timeout);
+
+        sender.send(9).unwrap();
+        assert_eq!(receiver.recv(), Ok(9));
+    })
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_closed_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = oneshot::channel::<u128>();
+        mem::drop(receiver);
+        sender.wait_closed();
+        assert!(sender.wait_closed_timeout(Duration::from_millis(0)));
+    })
+}
@@ -0,0 +1,174 @@
+//! Races the sender operations against every receiver operation. Under loom this explores all
+//! interleavings of the EMPTY, MESSAGE, RECEIVING, UNPARKING and DISCONNECTED transitions, and of
+//! the sender waiting for an acknowledgement or for the receiver to close, with the memory
+//! orderings the channel uses. The message is a `DropCounter`, so a message that is
+//! leaked, dropped twice or read before it was written is detected.
+
+#![cfg(all(feature = "alloc", feature = "std"))]
+
+use core::mem;
+use oneshot::{Receiver, Sender};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+type Message = DropCounter<u32>;
+
+/// Runs `send` on the sender while another thread runs `receive` on the receiver.
+///
+/// `send` returns true if the message was delivered. `receive` returns the message if it got it.
+/// If `blocking` is true it is expected to wait for the sender, so it must get the message
+/// exactly when it was delivered.
+fn race(
+    send: fn(Sender<Message>, Message) -> bool,
+    blocking: bool,
+    receive: fn(Receiver<Message>) -> Option<Message>,
+) {
+    maybe_loom_model(move || {
+        let (sender, receiver) = oneshot::channel();
+        let (message, counter) = DropCounter::new(7);
+
+        let t = thread::spawn(move || send(sender, message));
+
+        let received = receive(receiver);
+        let sent = t.join().unwrap();
+
+        if let Some(message) = &received {
+            assert!(sent);
+            assert_eq!(*message.value(), 7);
+        }
+        if blocking {
+            assert_eq!(received.is_some(), sent);
+        }
+
+        // The message is dropped exactly once, wherever it ended up.
+        mem::drop(received);
+        assert_eq!(counter.count(), 1);
+    })
+}
+
+fn send(sender: Sender<Message>, message: Message) -> bool {
+    sender.send(message).is_ok()
+}
+
+fn drop_sender(sender: Sender<Message>, message: Message) -> bool {
+    mem::drop(sender);
+    mem::drop(message);
+    false
+}
+
+fn send_acked(sender: Sender<Message>, message: Message) -> bool {
+    match sender.send_acked(message) {
+        Ok(delivery) => delivery.wait().is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn send_unless_closed(sender: Sender<Message>, message: Message) -> bool {
+    if sender.is_closed() {
+        return drop_sender(sender, message);
+    }
+    send(sender, message)
+}
+
+#[cfg(feature = "async")]
+fn send_unless_poll_closed(mut sender: Sender<Message>, message: Message) -> bool {
+    use core::task::Context;
+
+    let (waker, _wakeups) = helpers::counting_waker();
+    if sender
+        .poll_closed(&mut Context::from_waker(&waker))
+        .is_ready()
+    {
+        return drop_sender(sender, message);
+    }
+    send(sender, message)
+}
+
+/// Defines a module per sender operation, with one test per receive operation in each.
+macro_rules! transition_tests {
+    (senders: []; $($receivers:tt)*) => {};
+    (
+        senders: [$(#[$attr:meta])* $send:ident $(, $(#[$rest_attr:meta])* $rest:ident)*];
+        $($receivers:tt)*
+    ) => {
+        $(#[$attr])*
+        mod $send {
+            use super::*;
+            receive_tests!($send; $($receivers)*);
+        }
+        transition_tests!(senders: [$($(#[$rest_attr])* $rest),*]; $($receivers)*);
+    };
+}
+
+macro_rules! receive_tests {
+    ($send:ident; $($(#[$attr:meta])* $name:ident, blocking: $blocking:expr, $receive:expr;)*) => {
+        $(
+            $(#[$attr])*
+            #[test]
+            fn $name() {
+                race(super::$send, $blocking, $receive)
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "async")]
+fn poll(receiver: &mut Receiver<Message>) -> Option<Option<Message>> {
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    let (waker, _wakeups) = helpers::counting_waker();
+    match Pin::new(receiver).poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(result) => Some(result.ok()),
+        Poll::Pending => None,
+    }
+}
+
+transition_tests! {
+    senders: [
+        send,
+        drop_sender,
+        send_acked,
+        send_unless_closed,
+        #[cfg(feature = "async")]
+        send_unless_poll_closed
+    ];
+    try_recv, blocking: false, |receiver| receiver.try_recv().ok();
+    recv, blocking: true, |receiver| receiver.recv().ok();
+    recv_ref, blocking: true, |receiver| receiver.recv_ref().ok();
+    // The wall clock makes this one non-deterministic under loom once the sender can block as
+    // well. `recv_deadline_passed` covers the timeout transition in the models.
+    #[cfg(not(loom))]
+    recv_timeout, blocking: false, |receiver| {
+        receiver.recv_timeout(std::time::Duration::from_millis(1)).ok()
+    };
+    recv_deadline_passed, blocking: false, |receiver| {
+        receiver.recv_deadline(std::time::Instant::now()).ok()
+    };
+    drop_receiver, blocking: false, |receiver| {
+        mem::drop(receiver);
+        None
+    };
+    close, blocking: false, |receiver| receiver.close();
+    #[cfg(feature = "async")]
+    poll_then_drop, blocking: false, |mut receiver| poll(&mut receiver).flatten();
+    #[cfg(feature = "async")]
+    poll_then_recv, blocking: true, |mut receiver| match poll(&mut receiver) {
+        Some(result) => result,
+        None => receiver.recv().ok(),
+    };
+    #[cfg(feature = "async")]
+    poll_then_try_recv, blocking: false, |mut receiver| match poll(&mut receiver) {
+        Some(result) => result,
+        None => receiver.try_recv().ok(),
+    };
+}
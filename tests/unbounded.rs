@@ -0,0 +1,235 @@
+#![cfg(feature = "std")]
+
+use core::time::Duration;
+use oneshot::unbounded::{self, RecvError, RecvTimeoutError, SendError, TryRecvError};
+
+mod thread {
+    #[cfg(loom)]
+    pub use loom::thread::spawn;
+    #[cfg(not(loom))]
+    pub use std::thread::spawn;
+}
+
+mod helpers;
+use helpers::{maybe_loom_model, DropCounter};
+
+#[test]
+fn send_and_recv_in_order() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        for i in 0..3u32 {
+            sender.send(i).unwrap();
+        }
+        assert_eq!(receiver.try_recv(), Ok(0));
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv_timeout(Duration::from_millis(1)), Ok(2));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    })
+}
+
+#[test]
+fn recv_timeout_on_empty_channel() {
+    maybe_loom_model(|| {
+        let (_sender, receiver) = unbounded::channel::<u32>();
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(1)),
+            Err(RecvTimeoutError::Timeout)
+        );
+    })
+}
+
+#[test]
+fn recv_before_send() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        let t = thread::spawn(move || receiver.recv());
+        sender.send(19u128).unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+    })
+}
+
+#[test]
+fn concurrent_senders() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        let sender2 = sender.clone();
+        let t = thread::spawn(move || sender2.send(1u32).unwrap());
+        sender.send(2).unwrap();
+        drop(sender);
+        let first = receiver.recv().unwrap();
+        let second = receiver.recv().unwrap();
+        assert_eq!(first + second, 3);
+        assert_eq!(receiver.recv(), Err(RecvError));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn messages_remain_after_senders_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        let t = thread::spawn(move || sender.send(1u32).unwrap());
+        assert_eq!(receiver.recv(), Ok(1));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Disconnected));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn send_with_dropped_receiver() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        drop(receiver);
+        assert_eq!(sender.send(19u128), Err(SendError(19)));
+    })
+}
+
+#[test]
+fn messages_are_dropped() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        let (message1, counter1) = DropCounter::new(1u32);
+        let (message2, counter2) = DropCounter::new(2u32);
+        sender.send(message1).unwrap();
+        drop(receiver);
+        // Nobody can receive it, so the message is dropped with the last receiver.
+        assert_eq!(counter1.count(), 1);
+
+        let (sender, receiver) = unbounded::channel();
+        sender.send(message2).unwrap();
+        drop(sender);
+        drop(receiver);
+        assert_eq!(counter2.count(), 1);
+    })
+}
+
+#[test]
+fn drop_sender_while_receiving() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel::<u32>();
+        let t = thread::spawn(move || drop(sender));
+        assert_eq!(receiver.recv(), Err(RecvError));
+        t.join().unwrap();
+    })
+}
+
+#[test]
+fn cloned_receivers() {
+    maybe_loom_model(|| {
+        let (sender, receiver) = unbounded::channel();
+        let receiver2 = receiver.clone();
+        let t = thread::spawn(move || receiver2.recv().unwrap());
+        sender.send(1u32).unwrap();
+        sender.send(2).unwrap();
+        let received = receiver.recv().unwrap() + t.join().unwrap();
+        assert_eq!(received, 3);
+    })
+}
+
+#[cfg(not(loom))]
+#[test]
+fn many_senders_and_receivers() {
+    let (sender, receiver) = unbounded::channel();
+    let receivers: Vec<_> = (0..4)
+        .map(|_| {
+            let receiver = receiver.clone();
+            thread::spawn(move || std::iter::from_fn(|| receiver.recv().ok()).sum::<u64>())
+        })
+        .collect();
+    drop(receiver);
+    let senders: Vec<_> = (0..4u64)
+        .map(|i| {
+            let sender = sender.clone();
+            thread::spawn(move || (0..1000).for_each(|j| sender.send(i * 1000 + j).unwrap()))
+        })
+        .collect();
+    drop(sender);
+
+    senders.into_iter().for_each(|t| t.join().unwrap());
+    let sum: u64 = receivers.into_iter().map(|t| t.join().unwrap()).sum();
+    assert_eq!(sum, (0..4000).sum());
+}
+
+#[cfg(all(feature = "async", not(loom)))]
+mod asynchronous {
+    use super::*;
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    #[tokio::test]
+    async fn recv_async_from_thread() {
+        let (sender, receiver) = unbounded::channel();
+        let t = std::thread::spawn(move || (0..100u32).for_each(|i| sender.send(i).unwrap()));
+        for i in 0..100 {
+            assert_eq!(receiver.recv_async().await, Ok(i));
+        }
+        assert_eq!(receiver.recv_async().await, Err(RecvError));
+        t.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_from_task_to_thread() {
+        let (sender, receiver) = unbounded::channel();
+        let t = std::thread::spawn(move || receiver.recv());
+        tokio::spawn(async move { sender.send(19u128).unwrap() })
+            .await
+            .unwrap();
+        assert_eq!(t.join().unwrap(), Ok(19));
+    }
+
+    #[tokio::test]
+    async fn dropping_recv_future_loses_no_message() {
+        let (sender, receiver) = unbounded::channel();
+        let timeout = tokio::time::timeout(Duration::from_millis(1), receiver.recv_async());
+        assert!(timeout.await.is_err());
+        let t = std::thread::spawn(move || sender.send(19u128));
+        assert_eq!(receiver.recv(), Ok(19));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn recv_future_wakes_latest_waker() {
+        let (sender, receiver) = unbounded::channel();
+        let (waker1, wakeups1) = helpers::counting_waker();
+        let (waker2, wakeups2) = helpers::counting_waker();
+        let mut future = receiver.recv_async();
+        let poll1 = Pin::new(&mut future).poll(&mut Context::from_waker(&waker1));
+        let poll2 = Pin::new(&mut future).poll(&mut Context::from_waker(&waker2));
+        assert_eq!((poll1, poll2), (Poll::Pending, Poll::Pending));
+
+        sender.send(19u32).unwrap();
+        assert_eq!(wakeups1.load(core::sync::atomic::Ordering::SeqCst), 0);
+        assert_eq!(wakeups2.load(core::sync::atomic::Ordering::SeqCst), 1);
+        let poll = Pin::new(&mut future).poll(&mut Context::from_waker(&waker2));
+        assert_eq!(poll, Poll::Ready(Ok(19)));
+
+        drop(sender);
+        let poll = Pin::new(&mut receiver.recv_async()).poll(&mut Context::from_waker(&waker2));
+        assert_eq!(poll, Poll::Ready(Err(RecvError)));
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn stream_ends_when_senders_are_gone() {
+        use futures_core::Stream;
+
+        let (sender, mut receiver) = unbounded::channel();
+        let (waker, wakeups) = helpers::counting_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(Pin::new(&mut receiver).poll_next(&mut cx), Poll::Pending);
+
+        let t = std::thread::spawn(move || sender.send(1u32).unwrap());
+        t.join().unwrap();
+        assert_eq!(wakeups.load(core::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(Some(1))
+        );
+        assert_eq!(
+            Pin::new(&mut receiver).poll_next(&mut cx),
+            Poll::Ready(None)
+        );
+    }
+}